@@ -8,5 +8,10 @@
 [`UnixSeqpacketListener`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacketListener.html
 [`UnixSeqpacket`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacket.html
 [`UnixSeqpacket::pair()`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacket.html#method.pair
+[`UnixSeqpacketBuilder`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacketBuilder.html
+[`blocking`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/blocking/index.html
+[`format`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/format/index.html
+[`rpc`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/rpc/index.html
+[`service`]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/service/index.html
 [UnixSeqpacket::send_vectored_with_ancillary]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacket.html#method.send_vectored_with_ancillary
 [UnixSeqpacket::recv_vectored_with_ancillary]: https://docs.rs/tokio-seqpacket/latest/tokio_seqpacket/struct.UnixSeqpacket.html#method.recv_vectored_with_ancillary
@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+/// The address of a Unix seqpacket socket.
+///
+/// An address can be a path on the file system, an abstract name or it can be unnamed.
+/// Abstract names are only supported on Linux and Android.
+///
+/// Connected sockets that were not explicitly bound to an address are unnamed,
+/// just like both sockets of a pair created with [`UnixSeqpacket::pair()`](crate::UnixSeqpacket::pair).
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SocketAddr {
+	inner: Inner,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Inner {
+	Unnamed,
+	Pathname(PathBuf),
+	Abstract(Vec<u8>),
+}
+
+impl SocketAddr {
+	/// Create a socket address from a path on the file system.
+	///
+	/// An error is returned if the path contains a null byte or if it is too long.
+	pub fn from_pathname<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+		use std::os::unix::ffi::OsStrExt;
+		let path = path.as_ref();
+		if path.as_os_str().as_bytes().contains(&0) {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"path must not contain null bytes",
+			));
+		}
+		let address = Self::pathname(path.to_owned());
+		crate::sys::socket_addr_to_sockaddr(&address)?;
+		Ok(address)
+	}
+
+	/// Create a socket address with an abstract name.
+	///
+	/// The name should not include the leading null byte that is used by the kernel to mark abstract addresses.
+	/// An error is returned if the name is too long.
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	pub fn from_abstract_name<N: AsRef<[u8]>>(name: N) -> std::io::Result<Self> {
+		let address = Self::abstract_name(name.as_ref().to_vec());
+		crate::sys::socket_addr_to_sockaddr(&address)?;
+		Ok(address)
+	}
+
+	pub(crate) fn unnamed() -> Self {
+		Self { inner: Inner::Unnamed }
+	}
+
+	pub(crate) fn pathname(path: PathBuf) -> Self {
+		Self {
+			inner: Inner::Pathname(path),
+		}
+	}
+
+	pub(crate) fn abstract_name(name: Vec<u8>) -> Self {
+		Self {
+			inner: Inner::Abstract(name),
+		}
+	}
+
+	/// Get the path of the address, if it is a path on the file system.
+	pub fn as_pathname(&self) -> Option<&Path> {
+		match &self.inner {
+			Inner::Pathname(path) => Some(path),
+			_ => None,
+		}
+	}
+
+	/// Get the abstract name of the address, if it is an abstract address.
+	///
+	/// The returned name does not include the leading null byte that is used by the kernel to mark abstract addresses.
+	pub fn as_abstract_name(&self) -> Option<&[u8]> {
+		match &self.inner {
+			Inner::Abstract(name) => Some(name),
+			_ => None,
+		}
+	}
+
+	/// Check if the address is unnamed.
+	pub fn is_unnamed(&self) -> bool {
+		matches!(self.inner, Inner::Unnamed)
+	}
+}
+
+impl std::fmt::Debug for SocketAddr {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match &self.inner {
+			Inner::Unnamed => write!(f, "(unnamed)"),
+			Inner::Pathname(path) => write!(f, "{path:?} (pathname)"),
+			Inner::Abstract(name) => write!(f, "\"{}\" (abstract)", name.escape_ascii()),
+		}
+	}
+}
+
+/// Conversion to a [`SocketAddr`] for functions that bind or connect a socket.
+///
+/// This is implemented for [`SocketAddr`] and for anything that implements [`AsRef<Path>`].
+///
+/// For backwards compatibility, paths that start with a null byte are interpreted as abstract addresses on Linux and Android.
+/// The leading null byte is not part of the abstract name.
+/// New code should use [`SocketAddr::from_abstract_name()`] instead.
+pub trait ToSocketAddr {
+	/// Convert the value to a socket address.
+	fn to_socket_addr(&self) -> std::io::Result<SocketAddr>;
+}
+
+impl ToSocketAddr for SocketAddr {
+	fn to_socket_addr(&self) -> std::io::Result<SocketAddr> {
+		Ok(self.clone())
+	}
+}
+
+impl ToSocketAddr for &SocketAddr {
+	fn to_socket_addr(&self) -> std::io::Result<SocketAddr> {
+		Ok((*self).clone())
+	}
+}
+
+impl<P: AsRef<Path>> ToSocketAddr for P {
+	fn to_socket_addr(&self) -> std::io::Result<SocketAddr> {
+		use std::os::unix::ffi::OsStrExt;
+		let path = self.as_ref();
+		let bytes = path.as_os_str().as_bytes();
+		if cfg!(any(target_os = "linux", target_os = "android")) && bytes.first() == Some(&0) {
+			Ok(SocketAddr::abstract_name(bytes[1..].to_vec()))
+		} else {
+			Ok(SocketAddr::pathname(path.to_owned()))
+		}
+	}
+}
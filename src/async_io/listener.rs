@@ -0,0 +1,143 @@
+use async_io::Async;
+use filedesc::FileDesc;
+use std::os::raw::c_int;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::task::{Context, Poll};
+
+use super::UnixSeqpacket;
+use crate::readiness::{Direction, Readiness};
+use crate::{sys, SocketAddr, ToSocketAddr};
+
+/// Listener for Unix seqpacket sockets on the `async-io` reactor.
+///
+/// This is the `async-io` counterpart of [`crate::UnixSeqpacketListener`].
+pub struct UnixSeqpacketListener {
+	io: Async<FileDesc>,
+}
+
+impl std::fmt::Debug for UnixSeqpacketListener {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("UnixSeqpacketListener")
+			.field("fd", &self.io.get_ref().as_raw_fd())
+			.finish()
+	}
+}
+
+impl AsFd for UnixSeqpacketListener {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.io.get_ref().as_fd()
+	}
+}
+
+impl TryFrom<OwnedFd> for UnixSeqpacketListener {
+	type Error = std::io::Error;
+
+	/// Put a file descriptor in non-blocking mode and register it with the `async-io` reactor.
+	fn try_from(fd: OwnedFd) -> Result<Self, Self::Error> {
+		let io = Async::new(FileDesc::new(fd))?;
+		Ok(Self { io })
+	}
+}
+
+impl TryFrom<UnixSeqpacketListener> for OwnedFd {
+	type Error = std::io::Error;
+
+	/// Deregister the listener from the `async-io` reactor and return the inner file descriptor.
+	///
+	/// Deregistering the file descriptor from the reactor may fail,
+	/// so this conversion can fail.
+	fn try_from(listener: UnixSeqpacketListener) -> Result<Self, Self::Error> {
+		Ok(listener.into_file_desc()?.into_fd())
+	}
+}
+
+impl UnixSeqpacketListener {
+	/// Deregister the socket from the `async-io` reactor and return the inner file descriptor.
+	fn into_file_desc(self) -> std::io::Result<FileDesc> {
+		self.io.into_inner()
+	}
+
+	/// Bind a new seqpacket listener to the given address.
+	///
+	/// The created listener will be ready to accept new connections.
+	pub fn bind<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		Self::bind_with_backlog(address, 128)
+	}
+
+	/// Bind a new seqpacket listener to the given address.
+	///
+	/// The created listener will be ready to accept new connections.
+	///
+	/// The `backlog` parameter is used to determine the size of connection queue.
+	/// See `man 3 listen` for more information.
+	pub fn bind_with_backlog<A: ToSocketAddr>(address: A, backlog: c_int) -> std::io::Result<Self> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_non_blocking()?;
+		sys::bind(&socket, &address)?;
+		sys::listen(&socket, backlog)?;
+		let io = Async::new_nonblocking(socket)?;
+		Ok(Self { io })
+	}
+
+	/// Wrap a raw file descriptor as [`UnixSeqpacketListener`].
+	///
+	/// The file descriptor is put in non-blocking mode.
+	/// Registration of the file descriptor with the `async-io` reactor may fail.
+	/// For that reason, this function returns a [`std::io::Result`].
+	///
+	/// # Safety
+	/// This function is unsafe because the socket assumes it is the sole owner of the file descriptor.
+	/// Usage of this function could accidentally allow violating this contract
+	/// which can cause memory unsafety in code that relies on it being true.
+	pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> std::io::Result<Self> {
+		let io = Async::new(FileDesc::from_raw_fd(fd))?;
+		Ok(Self { io })
+	}
+
+	/// Get the raw file descriptor of the socket.
+	pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.io.get_ref().as_raw_fd()
+	}
+
+	/// Deregister the listener from the `async-io` reactor and return the inner file descriptor.
+	///
+	/// Deregistering the file descriptor from the reactor may fail.
+	/// For that reason, this function returns a [`std::io::Result`].
+	pub fn into_raw_fd(self) -> std::io::Result<std::os::unix::io::RawFd> {
+		Ok(self.into_file_desc()?.into_raw_fd())
+	}
+
+	/// Get the address the listener is bound to.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_local_address(self.io.get_ref())
+	}
+
+	/// Get and clear the value of the `SO_ERROR` option.
+	pub fn take_error(&self) -> std::io::Result<Option<std::io::Error>> {
+		sys::take_socket_error(self.io.get_ref())
+	}
+
+	/// Check if there is a connection ready to accept.
+	///
+	/// Note that unlike [`Self::accept`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	pub fn poll_accept(&self, cx: &mut Context) -> Poll<std::io::Result<UnixSeqpacket>> {
+		let socket = ready!(self.io.poll_io(cx, Direction::Read, sys::accept))?;
+		Poll::Ready(UnixSeqpacket::new(socket))
+	}
+
+	/// Accept a new incoming connection on the listener.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// Although no order is guaranteed, all calling tasks will try to complete the asynchronous action.
+	pub async fn accept(&self) -> std::io::Result<UnixSeqpacket> {
+		let socket = self.io.io(Direction::Read, sys::accept).await?;
+		UnixSeqpacket::new(socket)
+	}
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.as_raw_fd()
+	}
+}
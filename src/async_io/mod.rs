@@ -0,0 +1,13 @@
+//! Seqpacket sockets for the [`async-io`](https://docs.rs/async-io) reactor, as used by `smol`.
+//!
+//! The types in this module share the system call and ancillary data layers with the tokio types,
+//! and they expose the same API for sending and receiving messages with ancillary data.
+//! They do not need a tokio runtime.
+//!
+//! This module is only available with the `async-io` feature.
+
+mod listener;
+pub use listener::UnixSeqpacketListener;
+
+mod socket;
+pub use socket::UnixSeqpacket;
@@ -0,0 +1,284 @@
+use async_io::Async;
+use filedesc::FileDesc;
+use std::io::{IoSlice, IoSliceMut};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::task::{Context, Poll};
+
+use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
+use crate::readiness::{self, Direction, Readiness};
+use crate::{sys, MessageInfo, SocketAddr, ToSocketAddr, UCred};
+
+/// Unix seqpacket socket for the `async-io` reactor.
+///
+/// This is the `async-io` counterpart of [`crate::UnixSeqpacket`].
+/// See the documentation of that type for the semantics of peeking at messages with ancillary data.
+pub struct UnixSeqpacket {
+	io: Async<FileDesc>,
+}
+
+impl std::fmt::Debug for UnixSeqpacket {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("UnixSeqpacket")
+			.field("fd", &self.io.get_ref().as_raw_fd())
+			.finish()
+	}
+}
+
+impl AsFd for UnixSeqpacket {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.io.get_ref().as_fd()
+	}
+}
+
+impl TryFrom<OwnedFd> for UnixSeqpacket {
+	type Error = std::io::Error;
+
+	/// Put a file descriptor in non-blocking mode and register it with the `async-io` reactor.
+	fn try_from(fd: OwnedFd) -> Result<Self, Self::Error> {
+		let io = Async::new(FileDesc::new(fd))?;
+		Ok(Self { io })
+	}
+}
+
+impl TryFrom<UnixSeqpacket> for OwnedFd {
+	type Error = std::io::Error;
+
+	/// Deregister the socket from the `async-io` reactor and return the inner file descriptor.
+	///
+	/// Deregistering the file descriptor from the reactor may fail,
+	/// so this conversion can fail.
+	fn try_from(socket: UnixSeqpacket) -> Result<Self, Self::Error> {
+		Ok(socket.into_file_desc()?.into_fd())
+	}
+}
+
+impl UnixSeqpacket {
+	/// Register a non-blocking socket with the `async-io` reactor.
+	pub(crate) fn new(socket: FileDesc) -> std::io::Result<Self> {
+		let io = Async::new_nonblocking(socket)?;
+		Ok(Self { io })
+	}
+
+	/// Deregister the socket from the `async-io` reactor and return the inner file descriptor.
+	fn into_file_desc(self) -> std::io::Result<FileDesc> {
+		self.io.into_inner()
+	}
+
+	/// Connect a new seqpacket socket to the given address.
+	pub async fn connect<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_non_blocking()?;
+		if let Err(e) = sys::connect(&socket, &address) {
+			if e.kind() != std::io::ErrorKind::WouldBlock {
+				return Err(e);
+			}
+		}
+
+		let socket = Self::new(socket)?;
+		socket.io.writable().await?;
+		Ok(socket)
+	}
+
+	/// Create a pair of connected seqpacket sockets.
+	pub fn pair() -> std::io::Result<(Self, Self)> {
+		let (a, b) = sys::local_seqpacket_pair()?;
+		Ok((Self::new(a)?, Self::new(b)?))
+	}
+
+	/// Wrap a raw file descriptor as [`UnixSeqpacket`].
+	///
+	/// The file descriptor is put in non-blocking mode.
+	/// Registration of the file descriptor with the `async-io` reactor may fail.
+	/// For that reason, this function returns a [`std::io::Result`].
+	///
+	/// # Safety
+	/// This function is unsafe because the socket assumes it is the sole owner of the file descriptor.
+	/// Usage of this function could accidentally allow violating this contract
+	/// which can cause memory unsafety in code that relies on it being true.
+	pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> std::io::Result<Self> {
+		let io = Async::new(FileDesc::from_raw_fd(fd))?;
+		Ok(Self { io })
+	}
+
+	/// Get the raw file descriptor of the socket.
+	pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.io.get_ref().as_raw_fd()
+	}
+
+	/// Deregister the socket from the `async-io` reactor and return the inner file descriptor.
+	///
+	/// Deregistering the file descriptor from the reactor may fail.
+	/// For that reason, this function returns a [`std::io::Result`].
+	pub fn into_raw_fd(self) -> std::io::Result<std::os::unix::io::RawFd> {
+		Ok(self.into_file_desc()?.into_raw_fd())
+	}
+
+	/// Get the async-io [`Async`] wrapper of the socket.
+	pub fn as_async(&self) -> &Async<FileDesc> {
+		&self.io
+	}
+
+	/// Get the address of the local half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::local_addr()`] for more information.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_local_address(self.io.get_ref())
+	}
+
+	/// Get the address of the remote half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::peer_addr()`] for more information.
+	pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_peer_address(self.io.get_ref())
+	}
+
+	/// Get the effective credentials of the process which called `connect` or `pair`.
+	///
+	/// Note that this is not necessarily the process that currently has the file descriptor of the other side of the connection.
+	pub fn peer_cred(&self) -> std::io::Result<UCred> {
+		UCred::from_socket_peer(self.io.get_ref())
+	}
+
+	/// Get and clear the value of the `SO_ERROR` option.
+	pub fn take_error(&self) -> std::io::Result<Option<std::io::Error>> {
+		sys::take_socket_error(self.io.get_ref())
+	}
+
+	/// Try to send data with ancillary data on the socket to the connected peer without blocking.
+	///
+	/// If the send buffer is full, the current task is scheduled to wake up when the socket becomes writable.
+	pub fn poll_send_vectored_with_ancillary(
+		&self,
+		cx: &mut Context,
+		buffer: &[IoSlice],
+		ancillary: &mut AncillaryMessageWriter,
+	) -> Poll<std::io::Result<usize>> {
+		readiness::poll_send_msg(&self.io, cx, buffer, ancillary)
+	}
+
+	/// Send data on the socket to the connected peer.
+	pub async fn send(&self, buffer: &[u8]) -> std::io::Result<usize> {
+		self.io.io(Direction::Write, |fd| sys::send(fd, buffer)).await
+	}
+
+	/// Send data on the socket to the connected peer.
+	pub async fn send_vectored(&self, buffer: &[IoSlice<'_>]) -> std::io::Result<usize> {
+		self.send_vectored_with_ancillary(buffer, &mut AncillaryMessageWriter::new(&mut []))
+			.await
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer.
+	pub async fn send_with_ancillary(
+		&self,
+		buffer: &[u8],
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> std::io::Result<usize> {
+		self.send_vectored_with_ancillary(&[IoSlice::new(buffer)], ancillary)
+			.await
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer.
+	pub async fn send_vectored_with_ancillary(
+		&self,
+		buffer: &[IoSlice<'_>],
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> std::io::Result<usize> {
+		readiness::send_msg(&self.io, buffer, ancillary).await
+	}
+
+	/// Try to receive data with ancillary data on the socket from the connected peer without blocking.
+	///
+	/// If there is no message available, the current task is scheduled to wake up when the socket becomes readable.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	pub fn poll_recv_vectored_with_ancillary<'a>(
+		&self,
+		cx: &mut Context,
+		buffer: &mut [IoSliceMut],
+		ancillary_buffer: &'a mut [u8],
+	) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
+		readiness::poll_recv_msg(&self.io, cx, buffer, ancillary_buffer, 0)
+	}
+
+	/// Receive data on the socket from the connected peer.
+	pub async fn recv(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		self.recv_vectored(&mut [IoSliceMut::new(buffer)]).await
+	}
+
+	/// Peek at the next message on the socket from the connected peer.
+	///
+	/// Peeking a message with ancillary data may consume or omit it on some platforms.
+	/// See the [`crate::UnixSeqpacket`] documentation for more information.
+	pub async fn peek(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) =
+			readiness::recv_msg(&self.io, &mut [IoSliceMut::new(buffer)], &mut [], libc::MSG_PEEK).await?;
+		Ok(read)
+	}
+
+	/// Receive data on the socket from the connected peer.
+	pub async fn recv_vectored(&self, buffer: &mut [IoSliceMut<'_>]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) = readiness::recv_msg(&self.io, buffer, &mut [], 0).await?;
+		Ok(read)
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	pub async fn recv_with_ancillary<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		readiness::recv_msg(&self.io, &mut [IoSliceMut::new(buffer)], ancillary_buffer, 0).await
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	pub async fn recv_vectored_with_ancillary<'a>(
+		&self,
+		buffer: &mut [IoSliceMut<'_>],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		readiness::recv_msg(&self.io, buffer, ancillary_buffer, 0).await
+	}
+
+	/// Peek at the next message and its ancillary data on the socket from the connected peer.
+	///
+	/// Any objects in the ancillary data are duplicated.
+	/// They will be received again in subsequent calls to any peek or recv function.
+	/// Do note that the objects are *duplicated*, so file descriptors refer to the same kernel object, but they may have a different number.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub async fn peek_with_ancillary<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		readiness::recv_msg(
+			&self.io,
+			&mut [IoSliceMut::new(buffer)],
+			ancillary_buffer,
+			libc::MSG_PEEK,
+		)
+		.await
+	}
+
+	/// Shuts down the read, write, or both halves of this connection.
+	///
+	/// This function will cause all pending and future I/O calls on the
+	/// specified portions to immediately return with an appropriate value
+	/// (see the documentation of `Shutdown`).
+	pub fn shutdown(&self, how: std::net::Shutdown) -> std::io::Result<()> {
+		sys::shutdown(self.io.get_ref(), how)
+	}
+}
+
+impl AsRawFd for UnixSeqpacket {
+	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.as_raw_fd()
+	}
+}
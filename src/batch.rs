@@ -0,0 +1,96 @@
+use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
+use crate::MessageInfo;
+
+/// A message to send with [`UnixSeqpacket::send_batch()`](crate::UnixSeqpacket::send_batch).
+#[derive(Debug, Clone, Copy)]
+pub struct SendBatchEntry<'a> {
+	pub(crate) data: &'a [u8],
+	pub(crate) control: &'a [u8],
+}
+
+/// A buffer to receive a message with [`UnixSeqpacket::recv_batch()`](crate::UnixSeqpacket::recv_batch).
+///
+/// After a message has been received into the entry,
+/// [`Self::info()`] returns information about the message and [`Self::data()`] returns the received data.
+/// The ancillary data can be retrieved with [`Self::take_ancillary()`].
+///
+/// If the ancillary data is never taken, any file descriptors in it are closed when the entry is dropped
+/// or when it is used to receive a new message.
+#[derive(Debug)]
+pub struct RecvBatchEntry<'a> {
+	pub(crate) buffer: &'a mut [u8],
+	pub(crate) ancillary_buffer: &'a mut [u8],
+	pub(crate) info: Option<MessageInfo>,
+	pub(crate) ancillary_len: usize,
+}
+
+impl<'a> SendBatchEntry<'a> {
+	/// Create a new entry to send the given data without ancillary data.
+	pub fn new(data: &'a [u8]) -> Self {
+		Self { data, control: &[] }
+	}
+
+	/// Create a new entry to send the given data with ancillary data.
+	pub fn with_ancillary(data: &'a [u8], ancillary: &'a AncillaryMessageWriter<'_>) -> Self {
+		Self {
+			data,
+			control: &ancillary.buffer[..ancillary.length],
+		}
+	}
+}
+
+impl<'a> RecvBatchEntry<'a> {
+	/// Create a new entry to receive a message without ancillary data.
+	pub fn new(buffer: &'a mut [u8]) -> Self {
+		Self::with_ancillary(buffer, &mut [])
+	}
+
+	/// Create a new entry to receive a message with ancillary data.
+	pub fn with_ancillary(buffer: &'a mut [u8], ancillary_buffer: &'a mut [u8]) -> Self {
+		Self {
+			buffer,
+			ancillary_buffer,
+			info: None,
+			ancillary_len: 0,
+		}
+	}
+
+	/// Get information about the received message.
+	///
+	/// Returns `None` if no message has been received into this entry.
+	pub fn info(&self) -> Option<&MessageInfo> {
+		self.info.as_ref()
+	}
+
+	/// Get the data of the received message.
+	///
+	/// Returns an empty slice if no message has been received into this entry.
+	pub fn data(&self) -> &[u8] {
+		let len = self.info.as_ref().map_or(0, |info| info.bytes_read);
+		&self.buffer[..len]
+	}
+
+	/// Take the ancillary data of the received message.
+	///
+	/// The returned reader takes ownership of the objects in the ancillary data.
+	/// Calling this function again returns an empty reader.
+	pub fn take_ancillary(&mut self) -> AncillaryMessageReader<'_> {
+		let len = std::mem::take(&mut self.ancillary_len);
+		let truncated = self.info.as_ref().is_some_and(|info| info.ancillary_truncated);
+		// SAFETY: The buffer was filled by the kernel and we reset the length,
+		// so only one reader ever takes ownership of the objects in it.
+		unsafe { AncillaryMessageReader::new(&mut self.ancillary_buffer[..len], truncated) }
+	}
+
+	/// Close unclaimed objects from the previous message and forget about it.
+	pub(crate) fn reset(&mut self) {
+		drop(self.take_ancillary());
+		self.info = None;
+	}
+}
+
+impl Drop for RecvBatchEntry<'_> {
+	fn drop(&mut self) {
+		drop(self.take_ancillary())
+	}
+}
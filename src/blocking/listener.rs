@@ -0,0 +1,203 @@
+use filedesc::FileDesc;
+use std::os::raw::c_int;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd};
+
+use super::UnixSeqpacket;
+#[cfg(feature = "tokio")]
+use crate::listener::UnlinkGuard;
+use crate::{sys, SocketAddr, ToSocketAddr};
+
+/// Blocking listener for Unix seqpacket sockets.
+///
+/// This is the blocking counterpart of [`crate::UnixSeqpacketListener`].
+/// It does not need a tokio runtime.
+///
+/// You can convert between the blocking and the async listener with [`TryFrom`].
+/// The conversions are fallible because they change the blocking mode of the file descriptor with `fcntl()`,
+/// and because registering a file descriptor with the tokio runtime can fail.
+pub struct UnixSeqpacketListener {
+	fd: FileDesc,
+	#[cfg(feature = "tokio")]
+	local_address: Option<SocketAddr>,
+	#[cfg(feature = "tokio")]
+	unlink_guard: Option<UnlinkGuard>,
+}
+
+impl std::fmt::Debug for UnixSeqpacketListener {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("UnixSeqpacketListener")
+			.field("fd", &self.fd.as_raw_fd())
+			.finish()
+	}
+}
+
+impl AsFd for UnixSeqpacketListener {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.fd.as_fd()
+	}
+}
+
+impl From<OwnedFd> for UnixSeqpacketListener {
+	/// Wrap a file descriptor as blocking [`UnixSeqpacketListener`].
+	///
+	/// The file descriptor should refer to a listening seqpacket socket in blocking mode.
+	fn from(fd: OwnedFd) -> Self {
+		Self::new(FileDesc::new(fd))
+	}
+}
+
+impl From<UnixSeqpacketListener> for OwnedFd {
+	fn from(socket: UnixSeqpacketListener) -> Self {
+		socket.into_file_desc().into_fd()
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl TryFrom<crate::UnixSeqpacketListener> for UnixSeqpacketListener {
+	type Error = std::io::Error;
+
+	/// Deregister an async listener from the tokio runtime and put it in blocking mode.
+	///
+	/// If the async listener was created with [`UnixSeqpacketBuilder::cleanup()`](crate::UnixSeqpacketBuilder::cleanup),
+	/// the socket file is no longer removed when the listener is dropped.
+	fn try_from(listener: crate::UnixSeqpacketListener) -> std::io::Result<Self> {
+		let mut fd = FileDesc::new(OwnedFd::from(listener));
+		// SAFETY: We own the socket, so nobody else should be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut fd, false)?;
+		}
+		Ok(Self::new(fd))
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl TryFrom<UnixSeqpacketListener> for crate::UnixSeqpacketListener {
+	type Error = std::io::Error;
+
+	/// Put a blocking listener in non-blocking mode and register it with the tokio runtime.
+	///
+	/// Registration of the file descriptor with the tokio runtime may fail,
+	/// so this conversion can fail.
+	fn try_from(listener: UnixSeqpacketListener) -> std::io::Result<Self> {
+		let mut fd = listener.into_file_desc();
+		// SAFETY: We own the socket, so nobody else should be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut fd, true)?;
+		}
+		Self::try_from(fd.into_fd())
+	}
+}
+
+impl UnixSeqpacketListener {
+	pub(crate) fn new(fd: FileDesc) -> Self {
+		Self {
+			fd,
+			#[cfg(feature = "tokio")]
+			local_address: None,
+			#[cfg(feature = "tokio")]
+			unlink_guard: None,
+		}
+	}
+
+	/// Override the address reported by [`Self::local_addr()`].
+	#[cfg(feature = "tokio")]
+	pub(crate) fn set_local_address(&mut self, address: SocketAddr) {
+		self.local_address = Some(address);
+	}
+
+	/// Remove the socket file when the listener is dropped.
+	#[cfg(feature = "tokio")]
+	pub(crate) fn set_unlink_guard(&mut self, guard: UnlinkGuard) {
+		self.unlink_guard = Some(guard);
+	}
+
+	/// Return the inner file descriptor.
+	///
+	/// The socket file is no longer removed when the listener is dropped.
+	fn into_file_desc(self) -> FileDesc {
+		#[cfg(feature = "tokio")]
+		if let Some(guard) = self.unlink_guard {
+			guard.disarm();
+		}
+		self.fd
+	}
+
+	/// Bind a new seqpacket listener to the given address.
+	///
+	/// The created listener will be ready to accept new connections.
+	pub fn bind<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		Self::bind_with_backlog(address, 128)
+	}
+
+	/// Bind a new seqpacket listener to the given address.
+	///
+	/// The created listener will be ready to accept new connections.
+	///
+	/// The `backlog` parameter is used to determine the size of connection queue.
+	/// See `man 3 listen` for more information.
+	pub fn bind_with_backlog<A: ToSocketAddr>(address: A, backlog: c_int) -> std::io::Result<Self> {
+		let address = address.to_socket_addr()?;
+		let fd = sys::local_seqpacket_socket_blocking()?;
+		sys::bind(&fd, &address)?;
+		sys::listen(&fd, backlog)?;
+		Ok(Self::new(fd))
+	}
+
+	/// Wrap a raw file descriptor as blocking [`UnixSeqpacketListener`].
+	///
+	/// # Safety
+	/// This function is unsafe because the socket assumes it is the sole owner of the file descriptor.
+	/// Usage of this function could accidentally allow violating this contract
+	/// which can cause memory unsafety in code that relies on it being true.
+	pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> Self {
+		Self::new(FileDesc::from_raw_fd(fd))
+	}
+
+	/// Get the raw file descriptor of the socket.
+	pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.fd.as_raw_fd()
+	}
+
+	/// Return the inner file descriptor.
+	///
+	/// If the listener was created with [`UnixSeqpacketBuilder::cleanup()`](crate::UnixSeqpacketBuilder::cleanup),
+	/// the socket file is no longer removed when the listener is dropped.
+	pub fn into_raw_fd(self) -> std::os::unix::io::RawFd {
+		self.into_file_desc().into_raw_fd()
+	}
+
+	/// Get the address the listener is bound to.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		#[cfg(feature = "tokio")]
+		if let Some(address) = &self.local_address {
+			return Ok(address.clone());
+		}
+		sys::get_local_address(&self.fd)
+	}
+
+	/// Get and clear the value of the `SO_ERROR` option.
+	pub fn take_error(&self) -> std::io::Result<Option<std::io::Error>> {
+		sys::take_socket_error(&self.fd)
+	}
+
+	/// Accept a new incoming connection on the listener.
+	///
+	/// This blocks until a connection is available.
+	/// The accepted socket is in blocking mode.
+	pub fn accept(&self) -> std::io::Result<UnixSeqpacket> {
+		let fd = sys::accept_blocking(&self.fd)?;
+		Ok(UnixSeqpacket::new(fd))
+	}
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.as_raw_fd()
+	}
+}
+
+impl IntoRawFd for UnixSeqpacketListener {
+	fn into_raw_fd(self) -> std::os::unix::io::RawFd {
+		self.into_raw_fd()
+	}
+}
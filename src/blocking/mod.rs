@@ -0,0 +1,15 @@
+//! Blocking seqpacket sockets that do not need a tokio runtime.
+//!
+//! The types in this module are useful for command line tools and other programs that don't run an async runtime.
+//! They offer the same ancillary data API as the async types,
+//! and they can be converted to and from the async types.
+//!
+//! Timeouts for the blocking operations can be set with `set_read_timeout()` and `set_write_timeout()`.
+//! When a timeout expires, the operation fails with an error of kind [`std::io::ErrorKind::WouldBlock`] or [`std::io::ErrorKind::TimedOut`],
+//! depending on the platform.
+
+mod listener;
+pub use listener::UnixSeqpacketListener;
+
+mod socket;
+pub use socket::UnixSeqpacket;
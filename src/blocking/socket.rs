@@ -0,0 +1,303 @@
+use filedesc::FileDesc;
+use std::io::{IoSlice, IoSliceMut};
+use std::os::raw::c_int;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd};
+use std::time::Duration;
+
+use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
+use crate::{sys, MessageInfo, SocketAddr, ToSocketAddr, UCred};
+
+/// Blocking Unix seqpacket socket.
+///
+/// This is the blocking counterpart of [`crate::UnixSeqpacket`].
+/// It does not need a tokio runtime.
+///
+/// You can convert between the blocking and the async socket with [`TryFrom`].
+/// The conversions are fallible because they change the blocking mode of the file descriptor with `fcntl()`,
+/// and because registering a file descriptor with the tokio runtime can fail.
+pub struct UnixSeqpacket {
+	fd: FileDesc,
+}
+
+impl std::fmt::Debug for UnixSeqpacket {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("UnixSeqpacket")
+			.field("fd", &self.fd.as_raw_fd())
+			.finish()
+	}
+}
+
+impl AsFd for UnixSeqpacket {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.fd.as_fd()
+	}
+}
+
+impl From<OwnedFd> for UnixSeqpacket {
+	/// Wrap a file descriptor as blocking [`UnixSeqpacket`].
+	///
+	/// The file descriptor should refer to a seqpacket socket in blocking mode.
+	fn from(fd: OwnedFd) -> Self {
+		Self { fd: FileDesc::new(fd) }
+	}
+}
+
+impl From<UnixSeqpacket> for OwnedFd {
+	fn from(socket: UnixSeqpacket) -> Self {
+		socket.fd.into_fd()
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl TryFrom<crate::UnixSeqpacket> for UnixSeqpacket {
+	type Error = std::io::Error;
+
+	/// Deregister an async socket from the tokio runtime and put it in blocking mode.
+	fn try_from(socket: crate::UnixSeqpacket) -> std::io::Result<Self> {
+		let mut fd = FileDesc::new(OwnedFd::from(socket));
+		// SAFETY: We own the socket, so nobody else should be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut fd, false)?;
+		}
+		Ok(Self { fd })
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl TryFrom<UnixSeqpacket> for crate::UnixSeqpacket {
+	type Error = std::io::Error;
+
+	/// Put a blocking socket in non-blocking mode and register it with the tokio runtime.
+	///
+	/// Registration of the file descriptor with the tokio runtime may fail,
+	/// so this conversion can fail.
+	fn try_from(socket: UnixSeqpacket) -> std::io::Result<Self> {
+		let mut fd = socket.fd;
+		// SAFETY: We own the socket, so nobody else should be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut fd, true)?;
+		}
+		Self::try_from(fd.into_fd())
+	}
+}
+
+impl UnixSeqpacket {
+	pub(crate) fn new(fd: FileDesc) -> Self {
+		Self { fd }
+	}
+
+	/// Connect a new seqpacket socket to the given address.
+	pub fn connect<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		let address = address.to_socket_addr()?;
+		let fd = sys::local_seqpacket_socket_blocking()?;
+		sys::connect(&fd, &address)?;
+		Ok(Self::new(fd))
+	}
+
+	/// Create a pair of connected seqpacket sockets.
+	pub fn pair() -> std::io::Result<(Self, Self)> {
+		let (a, b) = sys::local_seqpacket_pair_blocking()?;
+		Ok((Self::new(a), Self::new(b)))
+	}
+
+	/// Wrap a raw file descriptor as blocking [`UnixSeqpacket`].
+	///
+	/// # Safety
+	/// This function is unsafe because the socket assumes it is the sole owner of the file descriptor.
+	/// Usage of this function could accidentally allow violating this contract
+	/// which can cause memory unsafety in code that relies on it being true.
+	pub unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> Self {
+		Self::new(FileDesc::from_raw_fd(fd))
+	}
+
+	/// Get the raw file descriptor of the socket.
+	pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.fd.as_raw_fd()
+	}
+
+	/// Return the inner file descriptor.
+	pub fn into_raw_fd(self) -> std::os::unix::io::RawFd {
+		self.fd.into_raw_fd()
+	}
+
+	/// Get the address of the local half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::local_addr()`] for more information.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_local_address(&self.fd)
+	}
+
+	/// Get the address of the remote half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::peer_addr()`] for more information.
+	pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_peer_address(&self.fd)
+	}
+
+	/// Get the effective credentials of the process which called `connect` or `pair`.
+	///
+	/// Note that this is not necessarily the process that currently has the file descriptor of the other side of the connection.
+	pub fn peer_cred(&self) -> std::io::Result<UCred> {
+		UCred::from_socket_peer(&self.fd)
+	}
+
+	/// Get and clear the value of the `SO_ERROR` option.
+	pub fn take_error(&self) -> std::io::Result<Option<std::io::Error>> {
+		sys::take_socket_error(&self.fd)
+	}
+
+	/// Get the read timeout of the socket (`SO_RCVTIMEO`).
+	///
+	/// A value of `None` means that receiving never times out.
+	pub fn read_timeout(&self) -> std::io::Result<Option<Duration>> {
+		sys::get_timeout(&self.fd, libc::SO_RCVTIMEO)
+	}
+
+	/// Set the read timeout of the socket (`SO_RCVTIMEO`).
+	///
+	/// If the timeout expires before a message is received, the receive functions fail
+	/// with an error of kind [`std::io::ErrorKind::WouldBlock`] or [`std::io::ErrorKind::TimedOut`], depending on the platform.
+	///
+	/// A value of `None` means that receiving never times out.
+	/// Passing a zero duration returns an error.
+	pub fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+		sys::set_timeout(&self.fd, libc::SO_RCVTIMEO, timeout)
+	}
+
+	/// Get the write timeout of the socket (`SO_SNDTIMEO`).
+	///
+	/// A value of `None` means that sending never times out.
+	pub fn write_timeout(&self) -> std::io::Result<Option<Duration>> {
+		sys::get_timeout(&self.fd, libc::SO_SNDTIMEO)
+	}
+
+	/// Set the write timeout of the socket (`SO_SNDTIMEO`).
+	///
+	/// If the timeout expires before a message can be sent, the send functions fail
+	/// with an error of kind [`std::io::ErrorKind::WouldBlock`] or [`std::io::ErrorKind::TimedOut`], depending on the platform.
+	///
+	/// A value of `None` means that sending never times out.
+	/// Passing a zero duration returns an error.
+	pub fn set_write_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+		sys::set_timeout(&self.fd, libc::SO_SNDTIMEO, timeout)
+	}
+
+	/// Send data on the socket to the connected peer.
+	pub fn send(&self, buffer: &[u8]) -> std::io::Result<usize> {
+		sys::send(&self.fd, buffer)
+	}
+
+	/// Send data on the socket to the connected peer.
+	pub fn send_vectored(&self, buffer: &[IoSlice<'_>]) -> std::io::Result<usize> {
+		self.send_vectored_with_ancillary(buffer, &mut AncillaryMessageWriter::new(&mut []))
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer.
+	pub fn send_with_ancillary(
+		&self,
+		buffer: &[u8],
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> std::io::Result<usize> {
+		self.send_vectored_with_ancillary(&[IoSlice::new(buffer)], ancillary)
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer.
+	pub fn send_vectored_with_ancillary(
+		&self,
+		buffer: &[IoSlice<'_>],
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> std::io::Result<usize> {
+		sys::send_msg(&self.fd, buffer, ancillary)
+	}
+
+	/// Receive data on the socket from the connected peer.
+	pub fn recv(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		self.recv_vectored(&mut [IoSliceMut::new(buffer)])
+	}
+
+	/// Peek at the next message on the socket from the connected peer.
+	///
+	/// Peeking a message with ancillary data may consume or omit it on some platforms.
+	/// See the [`crate::UnixSeqpacket`] documentation for more information.
+	pub fn peek(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) = self.recv_internal(&mut [IoSliceMut::new(buffer)], &mut [], libc::MSG_PEEK)?;
+		Ok(read)
+	}
+
+	/// Receive data on the socket from the connected peer.
+	pub fn recv_vectored(&self, buffer: &mut [IoSliceMut<'_>]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) = self.recv_internal(buffer, &mut [], 0)?;
+		Ok(read)
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	pub fn recv_with_ancillary<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		self.recv_internal(&mut [IoSliceMut::new(buffer)], ancillary_buffer, 0)
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	pub fn recv_vectored_with_ancillary<'a>(
+		&self,
+		buffer: &mut [IoSliceMut<'_>],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		self.recv_internal(buffer, ancillary_buffer, 0)
+	}
+
+	/// Peek at the next message and its ancillary data on the socket from the connected peer.
+	///
+	/// Any objects in the ancillary data are duplicated.
+	/// They will be received again in subsequent calls to any peek or recv function.
+	/// Do note that the objects are *duplicated*, so file descriptors refer to the same kernel object, but they may have a different number.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub fn peek_with_ancillary<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		self.recv_internal(&mut [IoSliceMut::new(buffer)], ancillary_buffer, libc::MSG_PEEK)
+	}
+
+	/// Shared implementation of the receive and peek functions.
+	fn recv_internal<'a>(
+		&self,
+		buffer: &mut [IoSliceMut<'_>],
+		ancillary_buffer: &'a mut [u8],
+		flags: c_int,
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		sys::recv_msg(&self.fd, buffer, ancillary_buffer, flags)
+	}
+
+	/// Shuts down the read, write, or both halves of this connection.
+	///
+	/// This function will cause all pending and future I/O calls on the
+	/// specified portions to immediately return with an appropriate value
+	/// (see the documentation of `Shutdown`).
+	pub fn shutdown(&self, how: std::net::Shutdown) -> std::io::Result<()> {
+		sys::shutdown(&self.fd, how)
+	}
+}
+
+impl AsRawFd for UnixSeqpacket {
+	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.as_raw_fd()
+	}
+}
+
+impl IntoRawFd for UnixSeqpacket {
+	fn into_raw_fd(self) -> std::os::unix::io::RawFd {
+		self.into_raw_fd()
+	}
+}
@@ -0,0 +1,438 @@
+use filedesc::FileDesc;
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+
+use crate::listener::UnlinkGuard;
+use crate::{blocking, sys, SocketAddr, ToSocketAddr, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Builder to configure seqpacket sockets before they are bound or connected.
+///
+/// Some socket options only have effect when they are set before the socket is bound or connected.
+/// The builder applies all configured options to the socket before calling `bind()`, `listen()` or `connect()`.
+///
+/// The sockets are created in non-blocking mode for the tokio runtime by default.
+/// Use [`Self::blocking_bind()`], [`Self::blocking_connect()`] or [`Self::blocking_pair()`]
+/// to create sockets in blocking mode for the [`blocking`] module instead.
+///
+/// # Example
+/// ```no_run
+/// # async fn foo() -> std::io::Result<()> {
+/// use tokio_seqpacket::UnixSeqpacketBuilder;
+///
+/// let listener = UnixSeqpacketBuilder::new()
+///     .recv_buffer_size(1024 * 1024)
+///     .mode(0o660)
+///     .bind("/run/foo.sock")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct UnixSeqpacketBuilder {
+	send_buffer_size: Option<usize>,
+	recv_buffer_size: Option<usize>,
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	passcred: Option<bool>,
+	mode: Option<u32>,
+	owner: Option<u32>,
+	group: Option<u32>,
+	backlog: c_int,
+	cleanup: bool,
+}
+
+impl Default for UnixSeqpacketBuilder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl UnixSeqpacketBuilder {
+	/// Create a new builder with the default options.
+	pub fn new() -> Self {
+		Self {
+			send_buffer_size: None,
+			recv_buffer_size: None,
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			passcred: None,
+			mode: None,
+			owner: None,
+			group: None,
+			backlog: 128,
+			cleanup: false,
+		}
+	}
+
+	/// Set the size of the send buffer of the socket (`SO_SNDBUF`).
+	///
+	/// See [`UnixSeqpacket::set_send_buffer_size()`] for more information.
+	pub fn send_buffer_size(mut self, size: usize) -> Self {
+		self.send_buffer_size = Some(size);
+		self
+	}
+
+	/// Set the size of the receive buffer of the socket (`SO_RCVBUF`).
+	///
+	/// See [`UnixSeqpacket::set_recv_buffer_size()`] for more information.
+	pub fn recv_buffer_size(mut self, size: usize) -> Self {
+		self.recv_buffer_size = Some(size);
+		self
+	}
+
+	/// Enable or disable the `SO_PASSCRED` option.
+	///
+	/// See [`UnixSeqpacket::set_passcred()`] for more information.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub fn passcred(mut self, enable: bool) -> Self {
+		self.passcred = Some(enable);
+		self
+	}
+
+	/// Set the file mode of the socket file created by [`Self::bind()`].
+	///
+	/// The socket is bound in a private temporary directory next to the requested path,
+	/// and the socket file is only linked into place after the mode has been applied.
+	/// This means that nobody can connect to the socket with looser permissions.
+	///
+	/// Binding to an abstract address with a file mode set results in an error,
+	/// since abstract addresses do not have a socket file.
+	pub fn mode(mut self, mode: u32) -> Self {
+		self.mode = Some(mode);
+		self
+	}
+
+	/// Set the owner of the socket file created by [`Self::bind()`].
+	///
+	/// Changing the owner usually requires the `CAP_CHOWN` capability.
+	/// The owner is applied before the socket file is linked into place, just like the [file mode](Self::mode).
+	pub fn owner(mut self, uid: u32) -> Self {
+		self.owner = Some(uid);
+		self
+	}
+
+	/// Set the group of the socket file created by [`Self::bind()`].
+	///
+	/// Unprivileged processes can only change the group to one of their own supplementary groups.
+	/// The group is applied before the socket file is linked into place, just like the [file mode](Self::mode).
+	pub fn group(mut self, gid: u32) -> Self {
+		self.group = Some(gid);
+		self
+	}
+
+	/// Set the size of the connection queue of a listener created by [`Self::bind()`].
+	///
+	/// The default is 128.
+	/// See `man 3 listen` for more information.
+	pub fn backlog(mut self, backlog: c_int) -> Self {
+		self.backlog = backlog;
+		self
+	}
+
+	/// Replace stale socket files and remove the socket file when the listener is dropped.
+	///
+	/// When enabled, [`Self::bind()`] checks if an existing socket file is stale by trying to connect to it.
+	/// If nobody is listening on the socket, the file is removed and replaced by the new socket.
+	/// If the existing file is not a socket or if someone is still listening on it,
+	/// binding fails with [`std::io::ErrorKind::AddrInUse`] as usual.
+	///
+	/// The created listener also removes the socket file when it is dropped,
+	/// but only if the file at the path is still the socket file that it created.
+	/// The socket file of another process that replaced ours is left alone.
+	///
+	/// This option has no effect for abstract addresses, since they do not have a socket file.
+	pub fn cleanup(mut self, enable: bool) -> Self {
+		self.cleanup = enable;
+		self
+	}
+
+	/// Bind a new seqpacket listener to the given address.
+	///
+	/// The created listener will be ready to accept new connections.
+	pub fn bind<A: ToSocketAddr>(&self, address: A) -> std::io::Result<UnixSeqpacketListener> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_non_blocking()?;
+		let bound = self.bind_socket(&socket, &address)?;
+		let mut listener = UnixSeqpacketListener::new(socket)?;
+		if let Some(local_address) = bound.local_address {
+			listener.set_local_address(local_address);
+		}
+		if let Some(guard) = bound.unlink_guard {
+			if self.cleanup {
+				listener.set_unlink_guard(guard);
+			} else {
+				guard.disarm();
+			}
+		}
+		Ok(listener)
+	}
+
+	/// Bind a new blocking seqpacket listener to the given address.
+	///
+	/// This is the same as [`Self::bind()`], except that the socket is created in blocking mode,
+	/// and it is returned as a [`blocking::UnixSeqpacketListener`] that does not need a tokio runtime.
+	pub fn blocking_bind<A: ToSocketAddr>(&self, address: A) -> std::io::Result<blocking::UnixSeqpacketListener> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_blocking()?;
+		let bound = self.bind_socket(&socket, &address)?;
+		let mut listener = blocking::UnixSeqpacketListener::new(socket);
+		if let Some(local_address) = bound.local_address {
+			listener.set_local_address(local_address);
+		}
+		if let Some(guard) = bound.unlink_guard {
+			if self.cleanup {
+				listener.set_unlink_guard(guard);
+			} else {
+				guard.disarm();
+			}
+		}
+		Ok(listener)
+	}
+
+	/// Connect a new seqpacket socket to the given address.
+	pub async fn connect<A: ToSocketAddr>(&self, address: A) -> std::io::Result<UnixSeqpacket> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_non_blocking()?;
+		self.configure(&socket)?;
+		if let Err(e) = sys::connect(&socket, &address) {
+			if e.kind() != std::io::ErrorKind::WouldBlock {
+				return Err(e);
+			}
+		}
+
+		let socket = UnixSeqpacket::new(socket)?;
+		socket.as_async_fd().writable().await?.retain_ready();
+		Ok(socket)
+	}
+
+	/// Connect a new seqpacket socket to the given address, blocking until the connection is established.
+	///
+	/// See [`UnixSeqpacket::connect_blocking()`] for more information.
+	pub fn connect_blocking<A: ToSocketAddr>(&self, address: A) -> std::io::Result<UnixSeqpacket> {
+		let address = address.to_socket_addr()?;
+		let mut socket = sys::local_seqpacket_socket_blocking()?;
+		self.configure(&socket)?;
+		if let Err(e) = sys::connect(&socket, &address) {
+			if e.kind() != std::io::ErrorKind::WouldBlock {
+				return Err(e);
+			}
+		}
+
+		// SAFETY: We just created the socket in this scope,
+		// no other thread can soundly be be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut socket, true)?;
+		}
+
+		UnixSeqpacket::new(socket)
+	}
+
+	/// Create a pair of connected seqpacket sockets.
+	///
+	/// The options are applied to both sockets.
+	pub fn pair(&self) -> std::io::Result<(UnixSeqpacket, UnixSeqpacket)> {
+		let (a, b) = sys::local_seqpacket_pair()?;
+		self.configure(&a)?;
+		self.configure(&b)?;
+		Ok((UnixSeqpacket::new(a)?, UnixSeqpacket::new(b)?))
+	}
+
+	/// Connect a new blocking seqpacket socket to the given address.
+	///
+	/// The socket is created in blocking mode,
+	/// and it is returned as a [`blocking::UnixSeqpacket`] that does not need a tokio runtime.
+	pub fn blocking_connect<A: ToSocketAddr>(&self, address: A) -> std::io::Result<blocking::UnixSeqpacket> {
+		let address = address.to_socket_addr()?;
+		let socket = sys::local_seqpacket_socket_blocking()?;
+		self.configure(&socket)?;
+		sys::connect(&socket, &address)?;
+		Ok(blocking::UnixSeqpacket::new(socket))
+	}
+
+	/// Create a pair of connected blocking seqpacket sockets.
+	///
+	/// The options are applied to both sockets.
+	/// The sockets are created in blocking mode and do not need a tokio runtime.
+	pub fn blocking_pair(&self) -> std::io::Result<(blocking::UnixSeqpacket, blocking::UnixSeqpacket)> {
+		let (a, b) = sys::local_seqpacket_pair_blocking()?;
+		self.configure(&a)?;
+		self.configure(&b)?;
+		Ok((blocking::UnixSeqpacket::new(a), blocking::UnixSeqpacket::new(b)))
+	}
+
+	/// Configure a socket and bind it to the given address, ready to accept connections.
+	///
+	/// Returns the state that the listener must keep for the bound socket.
+	fn bind_socket(&self, socket: &FileDesc, address: &SocketAddr) -> std::io::Result<BoundSocket> {
+		self.configure(socket)?;
+		if self.mode.is_some() || self.owner.is_some() || self.group.is_some() {
+			let unlink_guard = self.bind_in_private_dir(socket, address)?;
+			return Ok(BoundSocket {
+				// The socket is still bound to the temporary path as far as the kernel is concerned.
+				local_address: Some(address.clone()),
+				unlink_guard: Some(unlink_guard),
+			});
+		}
+
+		if let Err(e) = sys::bind(socket, address) {
+			if !self.cleanup || e.kind() != std::io::ErrorKind::AddrInUse || address.as_pathname().is_none() {
+				return Err(e);
+			}
+			remove_stale_socket(address)?;
+			sys::bind(socket, address)?;
+		}
+		// Create the guard right away, so the socket file is removed again if any of the next steps fail.
+		let unlink_guard = address.as_pathname().map(guard_new_socket_file).transpose()?;
+		sys::listen(socket, self.backlog)?;
+		Ok(BoundSocket {
+			local_address: None,
+			unlink_guard,
+		})
+	}
+
+	/// Bind a socket in a private directory, apply the file permissions and link the socket file into place.
+	///
+	/// The socket file only becomes visible at the requested path once it has the right permissions.
+	/// Linking fails if the path already exists, so an existing file is never replaced silently.
+	///
+	/// Returns a guard that removes the linked socket file again if the listener can not be created.
+	fn bind_in_private_dir(&self, socket: &FileDesc, address: &SocketAddr) -> std::io::Result<UnlinkGuard> {
+		use std::os::unix::fs::PermissionsExt;
+
+		let path = address.as_pathname().ok_or_else(|| {
+			std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"can only set the permissions of a socket bound to a path",
+			)
+		})?;
+		let parent = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => parent,
+			_ => Path::new("."),
+		};
+
+		let private_dir = PrivateDir::new(parent)?;
+		let temp_path = private_dir.socket_path();
+		sys::bind(socket, &SocketAddr::from_pathname(&temp_path)?)?;
+		if let Some(mode) = self.mode {
+			std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(mode))?;
+		}
+		if self.owner.is_some() || self.group.is_some() {
+			std::os::unix::fs::chown(&temp_path, self.owner, self.group)?;
+		}
+		sys::listen(socket, self.backlog)?;
+
+		if let Err(e) = link_socket_file(&temp_path, path) {
+			if !self.cleanup || e.kind() != std::io::ErrorKind::AddrInUse {
+				return Err(e);
+			}
+			remove_stale_socket(address)?;
+			link_socket_file(&temp_path, path)?;
+		}
+		let unlink_guard = guard_new_socket_file(path)?;
+		drop(private_dir);
+		Ok(unlink_guard)
+	}
+
+	/// Apply the configured socket options to a socket.
+	fn configure(&self, socket: &FileDesc) -> std::io::Result<()> {
+		if let Some(size) = self.send_buffer_size {
+			sys::set_buffer_size(socket, libc::SO_SNDBUF, size)?;
+		}
+		if let Some(size) = self.recv_buffer_size {
+			sys::set_buffer_size(socket, libc::SO_RCVBUF, size)?;
+		}
+		#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+		if let Some(enable) = self.passcred {
+			sys::set_bool_option(socket, libc::SO_PASSCRED, enable)?;
+		}
+		Ok(())
+	}
+}
+
+/// State of a bound socket that must be kept by the listener.
+struct BoundSocket {
+	/// The address to report as local address, if it differs from the address known by the kernel.
+	local_address: Option<SocketAddr>,
+
+	/// The guard that removes the socket file if creating the listener fails.
+	///
+	/// The listener only keeps the guard if cleanup is enabled.
+	unlink_guard: Option<UnlinkGuard>,
+}
+
+/// Create an [`UnlinkGuard`] for a socket file that was just created.
+///
+/// If the guard can not be created, the socket file is removed immediately.
+fn guard_new_socket_file(path: &Path) -> std::io::Result<UnlinkGuard> {
+	UnlinkGuard::new(path).inspect_err(|_| {
+		std::fs::remove_file(path).ok();
+	})
+}
+
+/// Remove a socket file if nobody is listening on it anymore.
+///
+/// Returns an error of kind [`std::io::ErrorKind::AddrInUse`] if the file is not a socket,
+/// or if the socket is still in use.
+fn remove_stale_socket(address: &SocketAddr) -> std::io::Result<()> {
+	use std::os::unix::fs::FileTypeExt;
+
+	let in_use = || std::io::Error::new(std::io::ErrorKind::AddrInUse, "address already in use");
+	let path = address.as_pathname().ok_or_else(in_use)?;
+	match std::fs::symlink_metadata(path) {
+		Ok(metadata) if metadata.file_type().is_socket() => (),
+		Ok(_) => return Err(in_use()),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(e),
+	}
+
+	// Only a refused connection proves that nobody is listening.
+	// If the connection is pending because the backlog is full, the socket is still in use.
+	let probe = sys::local_seqpacket_socket_non_blocking()?;
+	match sys::connect(&probe, address) {
+		Err(e) if e.raw_os_error() == Some(libc::ECONNREFUSED) => (),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+		_ => return Err(in_use()),
+	}
+
+	match std::fs::remove_file(path) {
+		Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+		_ => Ok(()),
+	}
+}
+
+/// Link a socket file to a new path, failing with [`std::io::ErrorKind::AddrInUse`] if the path already exists.
+fn link_socket_file(from: &Path, to: &Path) -> std::io::Result<()> {
+	std::fs::hard_link(from, to).map_err(|e| {
+		if e.kind() == std::io::ErrorKind::AlreadyExists {
+			std::io::Error::new(std::io::ErrorKind::AddrInUse, "address already in use")
+		} else {
+			e
+		}
+	})
+}
+
+/// Private temporary directory to bind a socket in.
+///
+/// The directory and the socket file in it are removed when dropped.
+struct PrivateDir {
+	path: PathBuf,
+}
+
+impl PrivateDir {
+	/// Create a new private directory in the given parent directory.
+	///
+	/// The name is kept short, since the full path of the socket file must fit in a `sockaddr_un`.
+	fn new(parent: &Path) -> std::io::Result<Self> {
+		let path = sys::make_temp_dir(&parent.join(".sock-XXXXXX"))?;
+		Ok(Self { path })
+	}
+
+	/// Get the path of the socket file in the directory.
+	fn socket_path(&self) -> PathBuf {
+		self.path.join("s")
+	}
+}
+
+impl Drop for PrivateDir {
+	fn drop(&mut self) {
+		std::fs::remove_file(self.socket_path()).ok();
+		std::fs::remove_dir(&self.path).ok();
+	}
+}
@@ -0,0 +1,191 @@
+use bytes::{BufMut, BytesMut};
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::os::fd::OwnedFd;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::ancillary::OwnedAncillaryMessage;
+use crate::stream::{DEFAULT_ANCILLARY_CAPACITY, DEFAULT_MESSAGE_CAPACITY};
+use crate::UnixSeqpacket;
+
+/// Adapter to run a [`Decoder`] and [`Encoder`] on a [`UnixSeqpacket`].
+///
+/// Unlike [`tokio_util::codec::Framed`], this adapter preserves message boundaries:
+/// every received message is decoded as exactly one frame, and every frame is encoded and sent as exactly one message.
+/// No data is buffered across messages.
+///
+/// The stream yields each decoded frame together with the file descriptors received in the same message.
+/// Similarly, the sink takes a frame together with the file descriptors to attach to the message.
+///
+/// If the decoder does not produce a frame from a message, or it leaves some data unused,
+/// the stream yields an error of kind [`std::io::ErrorKind::InvalidData`].
+///
+/// Messages are received directly into the read buffer of the codec, and frames are sent directly from the write buffer.
+/// If a message or its ancillary data does not fit in the receive capacity,
+/// the stream yields an error of kind [`std::io::ErrorKind::InvalidData`] and the message is lost.
+///
+/// Like with a [`SeqpacketStream`](crate::SeqpacketStream), an empty message without ancillary data ends the stream,
+/// and closing the sink shuts down the write half of the socket.
+#[derive(Debug)]
+pub struct SeqpacketFramed<C> {
+	socket: UnixSeqpacket,
+	codec: C,
+	message_capacity: usize,
+	read_buffer: BytesMut,
+	ancillary_buffer: Vec<u8>,
+	write_buffer: BytesMut,
+	write_fds: Vec<OwnedFd>,
+	pending: bool,
+	eof: bool,
+}
+
+impl<C> SeqpacketFramed<C> {
+	/// Create a new framed socket with the given codec and the default receive capacities.
+	///
+	/// The default capacities are 64 KiB for the message data, and 1 KiB for the ancillary data.
+	pub fn new(socket: UnixSeqpacket, codec: C) -> Self {
+		Self::with_capacity(socket, codec, DEFAULT_MESSAGE_CAPACITY, DEFAULT_ANCILLARY_CAPACITY)
+	}
+
+	/// Create a new framed socket with the given codec and receive capacities.
+	///
+	/// Received messages with more than `message_capacity` bytes of data,
+	/// or with more than `ancillary_capacity` bytes of ancillary data are reported as errors.
+	pub fn with_capacity(socket: UnixSeqpacket, codec: C, message_capacity: usize, ancillary_capacity: usize) -> Self {
+		Self {
+			socket,
+			codec,
+			message_capacity,
+			read_buffer: BytesMut::new(),
+			ancillary_buffer: vec![0; ancillary_capacity],
+			write_buffer: BytesMut::new(),
+			write_fds: Vec::new(),
+			pending: false,
+			eof: false,
+		}
+	}
+
+	/// Get a reference to the wrapped socket.
+	pub fn get_ref(&self) -> &UnixSeqpacket {
+		&self.socket
+	}
+
+	/// Get a reference to the codec.
+	pub fn codec(&self) -> &C {
+		&self.codec
+	}
+
+	/// Get a mutable reference to the codec.
+	pub fn codec_mut(&mut self) -> &mut C {
+		&mut self.codec
+	}
+
+	/// Consume the framed socket and return the wrapped socket.
+	///
+	/// A frame that was accepted by the sink but not yet flushed is dropped without being sent.
+	pub fn into_inner(self) -> UnixSeqpacket {
+		self.socket
+	}
+}
+
+impl<C: Decoder + Unpin> Stream for SeqpacketFramed<C> {
+	type Item = Result<(C::Item, Vec<OwnedFd>), C::Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		if this.eof {
+			return Poll::Ready(None);
+		}
+
+		this.read_buffer.clear();
+		this.read_buffer.reserve(this.message_capacity);
+		let mut read_buffer = (&mut this.read_buffer).limit(this.message_capacity);
+		let (info, ancillary) =
+			ready!(this
+				.socket
+				.poll_recv_buf_with_ancillary(cx, &mut read_buffer, &mut this.ancillary_buffer))?;
+
+		// Take ownership of the file descriptors first, so they are closed if the message is invalid.
+		let end_of_stream = info.bytes_read() == 0 && ancillary.is_empty();
+		let mut fds = Vec::new();
+		for message in ancillary.into_messages() {
+			if let OwnedAncillaryMessage::FileDescriptors(received) = message {
+				fds.extend(received);
+			}
+		}
+
+		if info.truncated() || info.ancillary_truncated() {
+			return Poll::Ready(Some(Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				"received message does not fit in the receive buffer",
+			)
+			.into())));
+		}
+
+		if end_of_stream {
+			this.eof = true;
+			return Poll::Ready(None);
+		}
+
+		let frame = this.codec.decode_eof(&mut this.read_buffer)?;
+		let frame = match frame {
+			Some(frame) => frame,
+			None => {
+				return Poll::Ready(Some(Err(std::io::Error::new(
+					std::io::ErrorKind::InvalidData,
+					"message does not contain a complete frame",
+				)
+				.into())))
+			},
+		};
+		if !this.read_buffer.is_empty() {
+			return Poll::Ready(Some(Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				"message contains trailing data after the frame",
+			)
+			.into())));
+		}
+
+		Poll::Ready(Some(Ok((frame, fds))))
+	}
+}
+
+impl<C, I> Sink<(I, Vec<OwnedFd>)> for SeqpacketFramed<C>
+where
+	C: Encoder<I> + Unpin,
+{
+	type Error = C::Error;
+
+	fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.poll_flush(cx)
+	}
+
+	fn start_send(self: Pin<&mut Self>, (frame, fds): (I, Vec<OwnedFd>)) -> Result<(), Self::Error> {
+		let this = self.get_mut();
+		debug_assert!(!this.pending, "start_send called without poll_ready");
+		this.write_buffer.clear();
+		this.codec.encode(frame, &mut this.write_buffer)?;
+		this.write_fds = fds;
+		this.pending = true;
+		Ok(())
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		let this = self.get_mut();
+		if this.pending {
+			let result = ready!(this.socket.poll_send_with_fds(cx, &this.write_buffer, &this.write_fds));
+			// Drop the frame even if sending failed, so we don't keep trying to send it.
+			this.pending = false;
+			this.write_fds.clear();
+			result?;
+		}
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		ready!(self.as_mut().poll_flush(cx))?;
+		Poll::Ready(self.socket.shutdown(std::net::Shutdown::Write).map_err(Into::into))
+	}
+}
@@ -0,0 +1,125 @@
+//! Serde support for file descriptors in values.
+//!
+//! Use this module with `#[serde(with = "tokio_seqpacket::format::fd")]` on fields that hold a file descriptor.
+//! When a value is sent with [`UnixSeqpacket::send_value()`], each file descriptor is added to the `SCM_RIGHTS` ancillary data of the message,
+//! and the field is encoded as the index of the file descriptor in the ancillary data.
+//! When a value is received with [`UnixSeqpacket::recv_value_with_fds()`], the indices are resolved back to the received file descriptors.
+//!
+//! Any type that implements [`AsFd`] can be serialized, such as [`OwnedFd`], [`BorrowedFd`] or [`std::fs::File`].
+//! Fields are always deserialized as [`OwnedFd`].
+//! Received file descriptors that are not claimed by any field are closed,
+//! and indices that do not refer to a received file descriptor are rejected.
+//!
+//! The file descriptors are only available while sending or receiving a value through a socket.
+//! Serializing or deserializing a value with file descriptors in any other way results in an error.
+//!
+//! # Example
+//! ```no_run
+//! # #[cfg(feature = "json")]
+//! # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::os::fd::OwnedFd;
+//! use tokio_seqpacket::format::Json;
+//! use tokio_seqpacket::UnixSeqpacket;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct OpenResult {
+//!     name: String,
+//!     #[serde(with = "tokio_seqpacket::format::fd")]
+//!     file: OwnedFd,
+//! }
+//!
+//! let (a, b) = UnixSeqpacket::pair()?;
+//! let file = std::fs::File::open("/etc/hostname")?;
+//! a.send_value(Json, &OpenResult { name: "hostname".into(), file: file.into() }).await?;
+//!
+//! let mut buffer = [0u8; 1024];
+//! let mut ancillary_buffer = [0u8; 64];
+//! let result: OpenResult = b.recv_value_with_fds(Json, &mut buffer, &mut ancillary_buffer).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`UnixSeqpacket::send_value()`]: crate::UnixSeqpacket::send_value
+//! [`UnixSeqpacket::recv_value_with_fds()`]: crate::UnixSeqpacket::recv_value_with_fds
+//! [`BorrowedFd`]: std::os::fd::BorrowedFd
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::os::fd::{AsFd, OwnedFd};
+
+use super::{Format, FormatError};
+
+thread_local! {
+	/// The file descriptors collected while encoding a value.
+	static ENCODE_FDS: RefCell<Option<Vec<OwnedFd>>> = const { RefCell::new(None) };
+
+	/// The received file descriptors available while decoding a value.
+	static DECODE_FDS: RefCell<Option<Vec<Option<OwnedFd>>>> = const { RefCell::new(None) };
+}
+
+/// Serialize a file descriptor as an index into the ancillary data of the message.
+///
+/// The file descriptor is duplicated, so the original does not need to outlive the serializer.
+pub fn serialize<F: AsFd, S: Serializer>(fd: &F, serializer: S) -> Result<S::Ok, S::Error> {
+	let fd = fd.as_fd().try_clone_to_owned().map_err(S::Error::custom)?;
+	let index = ENCODE_FDS.with_borrow_mut(|fds| {
+		let fds = fds.as_mut()?;
+		fds.push(fd);
+		Some(fds.len() - 1)
+	});
+	let index =
+		index.ok_or_else(|| S::Error::custom("file descriptors can only be serialized while sending a value"))?;
+	let index = u32::try_from(index).map_err(|_| S::Error::custom("too many file descriptors"))?;
+	index.serialize(serializer)
+}
+
+/// Deserialize a file descriptor from an index into the ancillary data of the message.
+///
+/// Each index can be claimed only once.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OwnedFd, D::Error> {
+	let index = u32::deserialize(deserializer)?;
+	DECODE_FDS.with_borrow_mut(|fds| {
+		let fds = fds
+			.as_mut()
+			.ok_or_else(|| D::Error::custom("file descriptors can only be deserialized while receiving a value"))?;
+		let count = fds.len();
+		let slot = fds.get_mut(index as usize).ok_or_else(|| {
+			D::Error::custom(format_args!(
+				"file descriptor index {index} is out of range: message has {count} file descriptors"
+			))
+		})?;
+		slot.take()
+			.ok_or_else(|| D::Error::custom(format_args!("file descriptor index {index} is used more than once")))
+	})
+}
+
+/// Encode a value and collect the file descriptors it contains.
+pub(crate) fn encode_with_fds<F, T>(format: &F, value: &T, buffer: &mut Vec<u8>) -> Result<Vec<OwnedFd>, FormatError>
+where
+	F: Format,
+	T: Serialize + ?Sized,
+{
+	let previous = ENCODE_FDS.replace(Some(Vec::new()));
+	let result = format.encode(value, buffer);
+	let fds = ENCODE_FDS.replace(previous).unwrap_or_default();
+	result?;
+	Ok(fds)
+}
+
+/// Decode a value, with the given file descriptors available for fields that hold a file descriptor.
+///
+/// File descriptors that are not claimed by the value are closed.
+pub(crate) fn decode_with_fds<F, T>(format: &F, data: &[u8], fds: Vec<OwnedFd>) -> Result<T, FormatError>
+where
+	F: Format,
+	T: DeserializeOwned,
+{
+	let fds = fds.into_iter().map(Some).collect();
+	let previous = DECODE_FDS.replace(Some(fds));
+	let result = format.decode(data);
+	// Close any unclaimed file descriptors.
+	drop(DECODE_FDS.replace(previous));
+	result
+}
@@ -0,0 +1,175 @@
+//! Serialization formats for sending and receiving serde values as messages.
+//!
+//! Each message holds exactly one value, encoded with a [`Format`].
+//! Use [`UnixSeqpacket::send_value()`] and [`UnixSeqpacket::recv_value()`] to send and receive values.
+//!
+//! The following formats are available through crate features:
+//! * [`Postcard`] with the `postcard` feature.
+//! * [`Bincode`] with the `bincode` feature.
+//! * [`Json`] with the `json` feature.
+//!
+//! Other formats can be used by implementing the [`Format`] trait.
+//!
+//! Values can also contain file descriptors, which are passed along as ancillary data.
+//! See the [`fd`] module for more information.
+//!
+//! [`UnixSeqpacket::send_value()`]: crate::UnixSeqpacket::send_value
+//! [`UnixSeqpacket::recv_value()`]: crate::UnixSeqpacket::recv_value
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub mod fd;
+
+/// Boxed error of a [`Format`].
+pub type FormatError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A serialization format to encode values into messages and decode them again.
+pub trait Format {
+	/// Encode a value by appending it to the buffer.
+	fn encode<T: Serialize + ?Sized>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), FormatError>;
+
+	/// Decode a value from the data of a message.
+	fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError>;
+}
+
+impl<F: Format + ?Sized> Format for &F {
+	fn encode<T: Serialize + ?Sized>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), FormatError> {
+		(**self).encode(value, buffer)
+	}
+
+	fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError> {
+		(**self).decode(data)
+	}
+}
+
+/// The [postcard](https://docs.rs/postcard) format.
+#[cfg(feature = "postcard")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl Format for Postcard {
+	fn encode<T: Serialize + ?Sized>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), FormatError> {
+		postcard::to_io(value, buffer)?;
+		Ok(())
+	}
+
+	fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError> {
+		Ok(postcard::from_bytes(data)?)
+	}
+}
+
+/// The [bincode](https://docs.rs/bincode) format with the default options of `bincode::serialize()`.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Format for Bincode {
+	fn encode<T: Serialize + ?Sized>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), FormatError> {
+		bincode::serialize_into(buffer, value)?;
+		Ok(())
+	}
+
+	fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError> {
+		Ok(bincode::deserialize(data)?)
+	}
+}
+
+/// The [JSON](https://docs.rs/serde_json) format.
+#[cfg(feature = "json")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Format for Json {
+	fn encode<T: Serialize + ?Sized>(&self, value: &T, buffer: &mut Vec<u8>) -> Result<(), FormatError> {
+		serde_json::to_writer(buffer, value)?;
+		Ok(())
+	}
+
+	fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, FormatError> {
+		Ok(serde_json::from_slice(data)?)
+	}
+}
+
+/// Error for sending or receiving a serde value.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ValueError {
+	/// Sending or receiving the message failed.
+	Io(std::io::Error),
+
+	/// Encoding the value failed.
+	Encode(FormatError),
+
+	/// Decoding the value failed.
+	Decode(FormatError),
+
+	/// The received message did not fit in the receive buffer.
+	///
+	/// The message has been consumed, and the part that did not fit in the buffer is lost.
+	Truncated {
+		/// The size of the receive buffer.
+		buffer_len: usize,
+	},
+
+	/// The ancillary data of the received message did not fit in the ancillary buffer.
+	///
+	/// The message has been consumed, and the file descriptors that did not fit in the buffer are lost.
+	AncillaryTruncated {
+		/// The size of the ancillary buffer.
+		buffer_len: usize,
+	},
+}
+
+impl std::fmt::Display for ValueError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Io(e) => write!(f, "{e}"),
+			Self::Encode(e) => write!(f, "failed to encode value: {e}"),
+			Self::Decode(e) => write!(f, "failed to decode value: {e}"),
+			Self::Truncated { buffer_len } => {
+				write!(
+					f,
+					"message was truncated: it does not fit in a buffer of {buffer_len} bytes"
+				)
+			},
+			Self::AncillaryTruncated { buffer_len } => {
+				write!(
+					f,
+					"ancillary data was truncated: it does not fit in a buffer of {buffer_len} bytes"
+				)
+			},
+		}
+	}
+}
+
+impl std::error::Error for ValueError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io(e) => Some(e),
+			Self::Encode(e) => Some(&**e),
+			Self::Decode(e) => Some(&**e),
+			Self::Truncated { .. } => None,
+			Self::AncillaryTruncated { .. } => None,
+		}
+	}
+}
+
+impl From<std::io::Error> for ValueError {
+	fn from(other: std::io::Error) -> Self {
+		Self::Io(other)
+	}
+}
+
+impl From<ValueError> for std::io::Error {
+	fn from(other: ValueError) -> Self {
+		match other {
+			ValueError::Io(e) => e,
+			ValueError::Encode(e) => std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+			other => std::io::Error::new(std::io::ErrorKind::InvalidData, other),
+		}
+	}
+}
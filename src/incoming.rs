@@ -0,0 +1,82 @@
+use futures_core::Stream;
+use std::borrow::BorrowMut;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{UCred, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Stream of incoming connections on a [`UnixSeqpacketListener`].
+///
+/// The listener can be borrowed or owned by the stream.
+/// See [`UnixSeqpacketListener::incoming()`] and [`UnixSeqpacketListener::into_stream()`].
+///
+/// The stream never ends: errors while accepting a connection are yielded as items.
+#[derive(Debug)]
+pub struct Incoming<L> {
+	listener: L,
+}
+
+/// Stream of incoming connections with the credentials of the connecting peer.
+///
+/// See [`Incoming::with_credentials()`].
+#[derive(Debug)]
+pub struct IncomingWithCredentials<L> {
+	listener: L,
+}
+
+impl<L: BorrowMut<UnixSeqpacketListener>> Incoming<L> {
+	pub(crate) fn new(listener: L) -> Self {
+		Self { listener }
+	}
+
+	/// Also yield the credentials of the peer for each accepted connection.
+	///
+	/// The credentials are retrieved right after accepting the connection.
+	/// If they can not be retrieved, the stream yields an error and the connection is closed.
+	pub fn with_credentials(self) -> IncomingWithCredentials<L> {
+		IncomingWithCredentials {
+			listener: self.listener,
+		}
+	}
+
+	/// Get a reference to the listener.
+	pub fn get_ref(&self) -> &UnixSeqpacketListener {
+		self.listener.borrow()
+	}
+
+	/// Consume the stream and return the wrapped listener.
+	pub fn into_inner(self) -> L {
+		self.listener
+	}
+}
+
+impl<L: BorrowMut<UnixSeqpacketListener>> IncomingWithCredentials<L> {
+	/// Get a reference to the listener.
+	pub fn get_ref(&self) -> &UnixSeqpacketListener {
+		self.listener.borrow()
+	}
+
+	/// Consume the stream and return the wrapped listener.
+	pub fn into_inner(self) -> L {
+		self.listener
+	}
+}
+
+impl<L: BorrowMut<UnixSeqpacketListener> + Unpin> Stream for Incoming<L> {
+	type Item = std::io::Result<UnixSeqpacket>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let socket = ready!(self.get_mut().listener.borrow_mut().poll_accept(cx));
+		Poll::Ready(Some(socket))
+	}
+}
+
+impl<L: BorrowMut<UnixSeqpacketListener> + Unpin> Stream for IncomingWithCredentials<L> {
+	type Item = std::io::Result<(UnixSeqpacket, UCred)>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let socket = ready!(self.get_mut().listener.borrow_mut().poll_accept(cx))?;
+		let credentials = socket.peer_cred()?;
+		Poll::Ready(Some(Ok((socket, credentials))))
+	}
+}
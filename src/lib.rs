@@ -10,6 +10,8 @@
 //! You can create a [`UnixSeqpacketListener`] to start accepting connections,
 //! or create a [`UnixSeqpacket`] to connect to a listening socket.
 //! You can also create a pair of connected sockets with [`UnixSeqpacket::pair()`].
+//! To configure a socket before it is bound or connected, use a [`UnixSeqpacketBuilder`].
+//! For programs without a tokio runtime, the [`blocking`] module provides blocking versions of the socket and listener.
 //!
 //! # Passing file descriptors and other ancillary data.
 //!
@@ -46,10 +48,32 @@
 //! This crate mostly exposes APIs for portable POSIX functionality.
 //! However, it also supports some OS-specific features, such sending and receiving credentials as ancillary data, and peeking at the contents of ancillary data.
 //! To avoid accidentally writing non-portable code, these are gated behind the `non-portable` crate feature.
+//!
+//! # Optional features
+//!
+//! Integration with other crates is available through the following crate features:
+//! * `futures`: `SeqpacketStream` implements the `Stream` and `Sink` traits from the `futures` crate,
+//!   and `UnixSeqpacketListener::incoming()` returns a `Stream` of incoming connections.
+//! * `codec`: `SeqpacketFramed` runs a `tokio_util::codec` decoder and encoder on each message.
+//! * `bytes`: `UnixSeqpacket::send_buf()` and `recv_buf()` send from a `bytes::Buf` and receive into the spare capacity of a `bytes::BufMut`.
+//! * `server`: `Server` accepts connections and runs a handler for each of them, with connection limits and graceful shutdown.
+//! * `rpc`: the [`rpc`] module provides request/response calls with correlation IDs, timeouts, cancellation and a server-side dispatcher.
+//! * `tower`: `SeqpacketClient` implements `tower::Service` for RPC calls, and the [`service`] module drives a `Service` for each connection.
+//! * `uring`: `UringSeqpacket` sends and receives messages with io_uring, using owned buffers (Linux only).
+//! * `serde`: `UnixSeqpacket::send_value()` and `recv_value()` send and receive one serde value per message.
+//!   The `postcard`, `bincode` and `json` features enable the formats in the [`format`][mod@format] module.
+//!
+//! # Async runtimes
+//!
+//! The socket types at the crate root use tokio, and are available with the `tokio` feature, which is enabled by default.
+//! The `async-io` feature adds the `async_io` module with sockets for the `async-io` reactor, as used by `smol`.
+//! Both backends share the same system call and ancillary data layers, so they expose the same API for messages with ancillary data.
+//! To use the crate without tokio, disable the default features.
 
 #![warn(missing_docs)]
 #![cfg_attr(feature = "doc-cfg", feature(doc_cfg))]
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 macro_rules! ready {
 	($e:expr) => {
 		match $e {
@@ -59,17 +83,84 @@ macro_rules! ready {
 	};
 }
 
+// Declared before the other modules, so they can use the macros.
+#[cfg(feature = "tokio")]
+#[macro_use]
+mod options;
+
+mod address;
 pub mod ancillary;
+#[cfg(feature = "async-io")]
+pub mod async_io;
+#[cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+mod batch;
+pub mod blocking;
 pub mod borrow_fd;
+#[cfg(feature = "tokio")]
+mod builder;
+#[cfg(feature = "codec")]
+mod codec;
+#[cfg(feature = "serde")]
+pub mod format;
+#[cfg(feature = "futures")]
+mod incoming;
+#[cfg(feature = "tokio")]
 mod listener;
+mod message;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod readiness;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "tower")]
+pub mod service;
+#[cfg(feature = "tokio")]
 mod socket;
+#[cfg(feature = "futures")]
+mod stream;
 mod sys;
+#[cfg(feature = "tokio")]
+mod systemd;
 mod ucred;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+mod uring;
 
+pub use address::{SocketAddr, ToSocketAddr};
+#[cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+pub use batch::{RecvBatchEntry, SendBatchEntry};
+#[cfg(feature = "tokio")]
+pub use builder::UnixSeqpacketBuilder;
+#[cfg(feature = "codec")]
+pub use codec::SeqpacketFramed;
+#[cfg(feature = "serde")]
+pub use format::ValueError;
+#[cfg(feature = "futures")]
+pub use incoming::{Incoming, IncomingWithCredentials};
+#[cfg(feature = "tokio")]
 pub use listener::UnixSeqpacketListener;
-pub use socket::{MessageInfo, UnixSeqpacket};
+pub use message::{MessageInfo, OutgoingMessage, ReceivedMessage};
+#[cfg(feature = "server")]
+pub use server::{ConnectionError, Server};
+#[cfg(feature = "tower")]
+pub use service::SeqpacketClient;
+#[cfg(feature = "tokio")]
+pub use socket::UnixSeqpacket;
+#[cfg(feature = "futures")]
+pub use stream::SeqpacketStream;
 pub use ucred::UCred;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub use uring::UringSeqpacket;
 
+#[cfg(feature = "tokio")]
 #[doc(hidden)]
 #[deprecated(
 	since = "0.4.0",
@@ -77,6 +168,7 @@ pub use ucred::UCred;
 )]
 pub type ReadHalf<'a> = &'a UnixSeqpacket;
 
+#[cfg(feature = "tokio")]
 #[doc(hidden)]
 #[deprecated(
 	since = "0.4.0",
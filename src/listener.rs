@@ -3,13 +3,18 @@ use std::os::raw::c_int;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd};
 use std::path::{Path, PathBuf};
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
 
-use crate::{sys, UnixSeqpacket};
+use crate::readiness::{Direction, Readiness};
+use crate::{sys, SocketAddr, ToSocketAddr, UnixSeqpacket, UnixSeqpacketBuilder};
 
 /// Listener for Unix seqpacket sockets.
 pub struct UnixSeqpacketListener {
 	io: AsyncFd<FileDesc>,
+	local_address: Option<SocketAddr>,
+	unlink_guard: Option<UnlinkGuard>,
 }
 
 impl std::fmt::Debug for UnixSeqpacketListener {
@@ -36,20 +41,47 @@ impl TryFrom<OwnedFd> for UnixSeqpacketListener {
 
 impl From<UnixSeqpacketListener> for OwnedFd {
 	fn from(socket: UnixSeqpacketListener) -> Self {
-		socket.io.into_inner().into_fd()
+		socket.into_file_desc().into_fd()
 	}
 }
 
 impl UnixSeqpacketListener {
-	fn new(socket: FileDesc) -> std::io::Result<Self> {
+	pub(crate) fn new(socket: FileDesc) -> std::io::Result<Self> {
 		let io = AsyncFd::new(socket)?;
-		Ok(Self { io })
+		Ok(Self {
+			io,
+			local_address: None,
+			unlink_guard: None,
+		})
+	}
+
+	/// Override the address reported by [`Self::local_addr()`].
+	pub(crate) fn set_local_address(&mut self, address: SocketAddr) {
+		self.local_address = Some(address);
+	}
+
+	/// Remove the socket file when the listener is dropped.
+	pub(crate) fn set_unlink_guard(&mut self, guard: UnlinkGuard) {
+		self.unlink_guard = Some(guard);
+	}
+
+	/// Deregister the socket from the tokio runtime and return the inner file descriptor.
+	///
+	/// The socket file is no longer removed when the listener is dropped.
+	fn into_file_desc(mut self) -> FileDesc {
+		if let Some(guard) = self.unlink_guard.take() {
+			guard.disarm();
+		}
+		self.io.into_inner()
 	}
 
 	/// Bind a new seqpacket listener to the given address.
 	///
 	/// The create listener will be ready to accept new connections.
-	pub fn bind<P: AsRef<Path>>(address: P) -> std::io::Result<Self> {
+	///
+	/// This fails if the socket file already exists, even if nobody is listening on it anymore.
+	/// Use [`UnixSeqpacketBuilder::cleanup()`] to replace stale socket files and remove the socket file when the listener is dropped.
+	pub fn bind<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
 		Self::bind_with_backlog(address, 128)
 	}
 
@@ -59,11 +91,78 @@ impl UnixSeqpacketListener {
 	///
 	/// The `backlog` parameter is used to determine the size of connection queue.
 	/// See `man 3 listen` for more information.
-	pub fn bind_with_backlog<P: AsRef<Path>>(address: P, backlog: c_int) -> std::io::Result<Self> {
-		let socket = sys::local_seqpacket_socket_non_blocking()?;
-		sys::bind(&socket, address)?;
-		sys::listen(&socket, backlog)?;
-		Self::new(socket)
+	///
+	/// Use [`UnixSeqpacketBuilder`] to configure the socket before it is bound.
+	pub fn bind_with_backlog<A: ToSocketAddr>(address: A, backlog: c_int) -> std::io::Result<Self> {
+		UnixSeqpacketBuilder::new().backlog(backlog).bind(address)
+	}
+
+	/// Bind a new seqpacket listener to the given path with the given file permissions.
+	///
+	/// The socket is bound in a private temporary directory next to `path`.
+	/// The `mode`, `owner` and `group` are applied to the socket file in the temporary directory,
+	/// after which it is linked into place.
+	/// This ensures that nobody can connect to the socket while it has looser permissions.
+	///
+	/// If `owner` or `group` is `None`, it is left unchanged.
+	/// Changing the owner usually requires the `CAP_CHOWN` capability.
+	///
+	/// An error is returned if any of the steps fail, including when `path` already exists.
+	/// No socket file is left behind in that case.
+	///
+	/// Note that the kernel still considers the socket to be bound to the temporary path.
+	/// [`Self::local_addr()`] reports `path`, but [`UnixSeqpacket::local_addr()`] of accepted sockets may report the temporary path on some platforms.
+	///
+	/// This is a shortcut for [`UnixSeqpacketBuilder::mode()`], [`owner()`](UnixSeqpacketBuilder::owner) and [`group()`](UnixSeqpacketBuilder::group).
+	pub fn bind_with_permissions<P: AsRef<Path>>(
+		path: P,
+		mode: u32,
+		owner: Option<u32>,
+		group: Option<u32>,
+	) -> std::io::Result<Self> {
+		let mut builder = UnixSeqpacketBuilder::new().mode(mode);
+		if let Some(owner) = owner {
+			builder = builder.owner(owner);
+		}
+		if let Some(group) = group {
+			builder = builder.group(group);
+		}
+		builder.bind(path.as_ref())
+	}
+
+	/// Take ownership of the seqpacket listeners passed in by systemd socket activation.
+	///
+	/// This reads the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment variables,
+	/// as described in `man 3 sd_listen_fds`.
+	/// Every file descriptor is checked to be a listening `AF_UNIX` / `SOCK_SEQPACKET` socket.
+	/// If any of them is not, an error is returned and no file descriptors are taken.
+	///
+	/// Each file descriptor can only be taken once per process.
+	/// Calling this function again returns only the file descriptors that have not been taken yet.
+	/// If the process was not started with socket activation, an empty list is returned.
+	///
+	/// The environment variables are left untouched.
+	/// Note that child processes will not inherit the file descriptors,
+	/// so you should not pass the environment variables on to them.
+	pub fn from_systemd() -> std::io::Result<Vec<Self>> {
+		crate::systemd::take_listen_fds(None)?
+			.into_iter()
+			.map(Self::new)
+			.collect()
+	}
+
+	/// Take ownership of the seqpacket listeners with the given name passed in by systemd socket activation.
+	///
+	/// The name is set with the `FileDescriptorName=` option in the socket unit.
+	/// If the service manager did not pass any names, no file descriptors match.
+	///
+	/// Only file descriptors with a matching name are checked and taken.
+	/// See [`Self::from_systemd()`] for more information.
+	pub fn from_systemd_named(name: &str) -> std::io::Result<Vec<Self>> {
+		crate::systemd::take_listen_fds(Some(name))?
+			.into_iter()
+			.map(Self::new)
+			.collect()
 	}
 
 	/// Wrap a raw file descriptor as [`UnixSeqpacket`].
@@ -85,13 +184,19 @@ impl UnixSeqpacketListener {
 	}
 
 	/// Deregister the socket from the tokio runtime and return the inner file descriptor.
+	///
+	/// If the listener was created with [`UnixSeqpacketBuilder::cleanup()`],
+	/// the socket file is no longer removed when the file descriptor is closed.
 	pub fn into_raw_fd(self) -> std::os::unix::io::RawFd {
-		self.io.into_inner().into_raw_fd()
+		self.into_file_desc().into_raw_fd()
 	}
 
-	/// Get the socket address of the local half of this connection.
-	pub fn local_addr(&self) -> std::io::Result<PathBuf> {
-		sys::get_local_address(self.io.get_ref())
+	/// Get the address the listener is bound to.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		match &self.local_address {
+			Some(address) => Ok(address.clone()),
+			None => sys::get_local_address(self.io.get_ref()),
+		}
 	}
 
 	/// Get and clear the value of the `SO_ERROR` option.
@@ -105,18 +210,10 @@ impl UnixSeqpacketListener {
 	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
 	///
 	/// Note that this function does not return a remote address for the accepted connection.
-	/// This is because connected Unix sockets are anonymous and have no meaningful address.
+	/// Use [`UnixSeqpacket::peer_addr()`] on the accepted socket if you need it.
 	pub fn poll_accept(&mut self, cx: &mut Context) -> Poll<std::io::Result<UnixSeqpacket>> {
-		let socket = loop {
-			let mut ready_guard = ready!(self.io.poll_read_ready(cx)?);
-
-			match ready_guard.try_io(|inner| sys::accept(inner.get_ref())) {
-				Ok(x) => break x?,
-				Err(_would_block) => continue,
-			}
-		};
-
-		Poll::Ready(Ok(UnixSeqpacket::new(socket)?))
+		let socket = ready!(self.io.poll_io(cx, Direction::Read, sys::accept))?;
+		Poll::Ready(UnixSeqpacket::new(socket))
 	}
 
 	/// Accept a new incoming connection on the listener.
@@ -125,21 +222,52 @@ impl UnixSeqpacketListener {
 	/// Although no order is guaranteed, all calling tasks will try to complete the asynchronous action.
 	///
 	/// Note that this function does not return a remote address for the accepted connection.
-	/// This is because connected Unix sockets are anonymous and have no meaningful address.
+	/// Use [`UnixSeqpacket::peer_addr()`] on the accepted socket if you need it.
 	pub async fn accept(&mut self) -> std::io::Result<UnixSeqpacket> {
-		let socket = loop {
-			let mut ready_guard = self.io.readable().await?;
-
-			match ready_guard.try_io(|inner| sys::accept(inner.get_ref())) {
-				Ok(x) => break x?,
-				Err(_would_block) => continue,
-			}
-		};
+		let socket = self.io.io(Direction::Read, sys::accept).await?;
+		UnixSeqpacket::new(socket)
+	}
 
+	/// Try to accept a new incoming connection on the listener, without waiting for a connection to arrive.
+	///
+	/// If no connection is known to be pending, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// In that case, the readiness of the listener is cleared.
+	///
+	/// Unlike [`Self::poll_accept`], this does not register the current task for wake-up.
+	pub fn try_accept(&mut self) -> std::io::Result<UnixSeqpacket> {
+		let socket = self.io.try_io(Interest::READABLE, sys::accept)?;
 		UnixSeqpacket::new(socket)
 	}
+
+	/// Accept a new incoming connection on the listener with a timeout.
+	///
+	/// If no connection is accepted before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned and no connection is consumed.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn accept_timeout(&mut self, timeout: Duration) -> std::io::Result<UnixSeqpacket> {
+		crate::socket::with_timeout(timeout, self.accept()).await
+	}
+
+	/// Get a stream of incoming connections that borrows the listener.
+	///
+	/// Note that unlike [`Self::accept`], only the last task polling the stream will be woken up.
+	#[cfg(feature = "futures")]
+	pub fn incoming(&mut self) -> crate::Incoming<&mut Self> {
+		crate::Incoming::new(self)
+	}
+
+	/// Convert the listener into a stream of incoming connections.
+	///
+	/// Note that unlike [`Self::accept`], only the last task polling the stream will be woken up.
+	#[cfg(feature = "futures")]
+	pub fn into_stream(self) -> crate::Incoming<Self> {
+		crate::Incoming::new(self)
+	}
 }
 
+impl_socket_options!(UnixSeqpacketListener);
+
 impl AsRawFd for UnixSeqpacketListener {
 	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
 		self.as_raw_fd()
@@ -151,3 +279,48 @@ impl IntoRawFd for UnixSeqpacketListener {
 		self.into_raw_fd()
 	}
 }
+
+/// Guard that removes a socket file when dropped.
+///
+/// The file is only removed if it is still the same file that was created when the socket was bound.
+/// This prevents deleting the socket file of another process that replaced ours.
+#[derive(Debug)]
+pub(crate) struct UnlinkGuard {
+	path: PathBuf,
+	device: u64,
+	inode: u64,
+}
+
+impl UnlinkGuard {
+	/// Create a guard for the socket file at the given path.
+	///
+	/// This records the device and inode number of the file as it exists right now.
+	pub(crate) fn new(path: &Path) -> std::io::Result<Self> {
+		use std::os::unix::fs::MetadataExt;
+		let metadata = std::fs::symlink_metadata(path)?;
+		Ok(Self {
+			path: path.to_owned(),
+			device: metadata.dev(),
+			inode: metadata.ino(),
+		})
+	}
+
+	/// Drop the guard without removing the socket file.
+	pub(crate) fn disarm(mut self) {
+		self.path = PathBuf::new();
+	}
+}
+
+impl Drop for UnlinkGuard {
+	fn drop(&mut self) {
+		use std::os::unix::fs::MetadataExt;
+		if self.path.as_os_str().is_empty() {
+			return;
+		}
+		if let Ok(metadata) = std::fs::symlink_metadata(&self.path) {
+			if metadata.dev() == self.device && metadata.ino() == self.inode {
+				std::fs::remove_file(&self.path).ok();
+			}
+		}
+	}
+}
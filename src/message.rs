@@ -0,0 +1,175 @@
+use std::os::fd::OwnedFd;
+
+/// Information about a received seqpacket message.
+#[derive(Debug, Clone)]
+pub struct MessageInfo {
+	pub(crate) bytes_read: usize,
+	pub(crate) truncated: bool,
+	pub(crate) ancillary_truncated: bool,
+}
+
+impl MessageInfo {
+	/// Get the number of data bytes that were read.
+	///
+	/// A value of zero either indicates an empty seqpacket message or that the sender closed
+	/// their half of the connection and all messages have been received.
+	pub fn bytes_read(&self) -> usize {
+		self.bytes_read
+	}
+
+	/// Check if the message was truncated due to insufficient buffer space.
+	///
+	/// The truncated bytes of a received message are lost forever.
+	/// As such, you should ensure that your buffer is large enough for the expected messages,
+	/// or [peek] at the message first to dynamically resize the receive buffer if necessary.
+	/// But keep in mind that peeking at a message may consume the ancillary data, making it impossible to retrieve later.
+	///
+	/// [peek]: crate::UnixSeqpacket::peek
+	pub fn truncated(&self) -> bool {
+		self.truncated
+	}
+
+	/// Check if the ancillary data was truncated due to insufficient buffer space.
+	///
+	/// The truncated ancillary data of a received message is lost forever.
+	/// As such, you should ensure that your ancillary buffer is large enough for the expected messages.
+	/// Alternatively, if your platform supports it (like Linux and Android),
+	/// you can [peek] at the message first to dynamically resize the ancillary buffer as necessary.
+	///
+	/// [peek]: crate::UnixSeqpacket::peek_with_ancillary
+	pub fn ancillary_truncated(&self) -> bool {
+		self.ancillary_truncated
+	}
+}
+
+/// A received seqpacket message with owned data and ancillary objects.
+///
+/// Unlike the [`MessageInfo`](crate::MessageInfo) and [`AncillaryMessageReader`](crate::ancillary::AncillaryMessageReader) returned by the other receive functions,
+/// this type does not borrow any buffers from the caller.
+#[derive(Debug, Default)]
+pub struct ReceivedMessage {
+	/// The data of the message.
+	///
+	/// An empty message either indicates an empty seqpacket message or that the sender closed
+	/// their half of the connection and all messages have been received.
+	pub data: Vec<u8>,
+
+	/// The file descriptors received with the message.
+	pub fds: Vec<OwnedFd>,
+
+	/// The Unix credentials received with the message.
+	#[cfg(all(
+		feature = "non-portable",
+		any(target_os = "android", target_os = "linux", target_os = "netbsd")
+	))]
+	pub credentials: Vec<crate::UCred>,
+}
+
+impl ReceivedMessage {
+	/// Create a message from received data and ancillary data.
+	///
+	/// This takes ownership of all file descriptors in the ancillary data.
+	/// Unrecognized control messages are discarded.
+	#[cfg(any(
+		feature = "futures",
+		feature = "rpc",
+		all(
+			feature = "tokio",
+			feature = "non-portable",
+			any(target_os = "linux", target_os = "android")
+		)
+	))]
+	pub(crate) fn from_parts(data: Vec<u8>, ancillary: crate::ancillary::AncillaryMessageReader<'_>) -> Self {
+		use crate::ancillary::OwnedAncillaryMessage;
+
+		let mut message = Self {
+			data,
+			..Self::default()
+		};
+		for cmsg in ancillary.into_messages() {
+			match cmsg {
+				OwnedAncillaryMessage::FileDescriptors(fds) => message.fds.extend(fds),
+				#[cfg(all(
+					feature = "non-portable",
+					any(target_os = "android", target_os = "linux", target_os = "netbsd")
+				))]
+				OwnedAncillaryMessage::Credentials(credentials) => message.credentials.extend(credentials),
+				OwnedAncillaryMessage::Other(_) => (),
+			}
+		}
+		message
+	}
+}
+
+/// A seqpacket message to send, with owned data and file descriptors.
+#[derive(Debug, Default)]
+pub struct OutgoingMessage {
+	/// The data of the message.
+	pub data: Vec<u8>,
+
+	/// The file descriptors to attach to the message.
+	///
+	/// The file descriptors are closed when the message is dropped, after it has been sent.
+	pub fds: Vec<OwnedFd>,
+}
+
+impl OutgoingMessage {
+	/// Create a new message with the given data and no file descriptors.
+	pub fn new(data: impl Into<Vec<u8>>) -> Self {
+		Self {
+			data: data.into(),
+			fds: Vec::new(),
+		}
+	}
+
+	/// Get the size of the ancillary buffer needed to send this message.
+	#[cfg(feature = "tokio")]
+	pub(crate) fn ancillary_buffer_len(&self) -> std::io::Result<usize> {
+		fds_ancillary_buffer_len(&self.fds)
+	}
+
+	/// Write the control messages for this message into the given buffer.
+	///
+	/// The buffer should be at least [`Self::ancillary_buffer_len()`] bytes large.
+	#[cfg(feature = "tokio")]
+	pub(crate) fn ancillary_writer<'a>(
+		&'a self,
+		buffer: &'a mut [u8],
+	) -> std::io::Result<crate::ancillary::AncillaryMessageWriter<'a>> {
+		fds_ancillary_writer(&self.fds, buffer)
+	}
+}
+
+/// Get the size of the ancillary buffer needed to send the given file descriptors.
+#[cfg(feature = "tokio")]
+pub(crate) fn fds_ancillary_buffer_len(fds: &[OwnedFd]) -> std::io::Result<usize> {
+	if fds.is_empty() {
+		return Ok(0);
+	}
+	let fds_len = u32::try_from(fds.len() * std::mem::size_of::<std::os::fd::RawFd>())
+		.map_err(|_| std::io::ErrorKind::InvalidInput)?;
+	// SAFETY: CMSG_SPACE only does arithmetic on its argument.
+	let cmsg_space = unsafe { libc::CMSG_SPACE(fds_len) } as usize;
+	Ok(cmsg_space + crate::ancillary::AncillaryMessageWriter::BUFFER_ALIGN)
+}
+
+/// Write the control messages for the given file descriptors into the given buffer.
+///
+/// The buffer should be at least [`fds_ancillary_buffer_len()`] bytes large.
+#[cfg(feature = "tokio")]
+pub(crate) fn fds_ancillary_writer<'a>(
+	fds: &'a [OwnedFd],
+	buffer: &'a mut [u8],
+) -> std::io::Result<crate::ancillary::AncillaryMessageWriter<'a>> {
+	let mut ancillary = crate::ancillary::AncillaryMessageWriter::new(buffer);
+	if !fds.is_empty() {
+		ancillary.add_fds(fds)?;
+	}
+	Ok(ancillary)
+}
+
+impl From<Vec<u8>> for OutgoingMessage {
+	fn from(data: Vec<u8>) -> Self {
+		Self::new(data)
+	}
+}
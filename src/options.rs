@@ -0,0 +1,106 @@
+/// Implement the socket option accessors that are shared by sockets and listeners.
+///
+/// The type must have an `io` field that gives access to the [`FileDesc`](filedesc::FileDesc) of the socket with `get_ref()`.
+macro_rules! impl_socket_options {
+	($type:ident) => {
+		impl $type {
+			/// Get the size of the send buffer of the socket (`SO_SNDBUF`).
+			///
+			/// Note that Linux reports double the size that was set, to account for bookkeeping overhead.
+			pub fn send_buffer_size(&self) -> std::io::Result<usize> {
+				$crate::sys::get_buffer_size(self.io.get_ref(), libc::SO_SNDBUF)
+			}
+
+			/// Set the size of the send buffer of the socket (`SO_SNDBUF`).
+			///
+			/// The operating system may clamp the size to a system-wide minimum and maximum.
+			pub fn set_send_buffer_size(&self, size: usize) -> std::io::Result<()> {
+				$crate::sys::set_buffer_size(self.io.get_ref(), libc::SO_SNDBUF, size)
+			}
+
+			/// Get the size of the receive buffer of the socket (`SO_RCVBUF`).
+			///
+			/// Note that Linux reports double the size that was set, to account for bookkeeping overhead.
+			pub fn recv_buffer_size(&self) -> std::io::Result<usize> {
+				$crate::sys::get_buffer_size(self.io.get_ref(), libc::SO_RCVBUF)
+			}
+
+			/// Set the size of the receive buffer of the socket (`SO_RCVBUF`).
+			///
+			/// The operating system may clamp the size to a system-wide minimum and maximum.
+			pub fn set_recv_buffer_size(&self, size: usize) -> std::io::Result<()> {
+				$crate::sys::set_buffer_size(self.io.get_ref(), libc::SO_RCVBUF, size)
+			}
+
+			/// Set the size of the send buffer of the socket, ignoring the system-wide maximum (`SO_SNDBUFFORCE`).
+			///
+			/// This requires the `CAP_NET_ADMIN` capability.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn set_send_buffer_size_force(&self, size: usize) -> std::io::Result<()> {
+				$crate::sys::set_buffer_size(self.io.get_ref(), libc::SO_SNDBUFFORCE, size)
+			}
+
+			/// Set the size of the receive buffer of the socket, ignoring the system-wide maximum (`SO_RCVBUFFORCE`).
+			///
+			/// This requires the `CAP_NET_ADMIN` capability.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn set_recv_buffer_size_force(&self, size: usize) -> std::io::Result<()> {
+				$crate::sys::set_buffer_size(self.io.get_ref(), libc::SO_RCVBUFFORCE, size)
+			}
+
+			/// Check if the `SO_PASSCRED` option is enabled.
+			///
+			/// See [`Self::set_passcred()`] for more information.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn passcred(&self) -> std::io::Result<bool> {
+				$crate::sys::get_bool_option(self.io.get_ref(), libc::SO_PASSCRED)
+			}
+
+			/// Enable or disable the `SO_PASSCRED` option.
+			///
+			/// When enabled, every received message carries the credentials of the sender as ancillary data,
+			/// even if the sender did not explicitly attach them.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn set_passcred(&self, enable: bool) -> std::io::Result<()> {
+				$crate::sys::set_bool_option(self.io.get_ref(), libc::SO_PASSCRED, enable)
+			}
+
+			/// Check if the `SO_PASSSEC` option is enabled.
+			///
+			/// See [`Self::set_passsec()`] for more information.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn passsec(&self) -> std::io::Result<bool> {
+				$crate::sys::get_bool_option(self.io.get_ref(), libc::SO_PASSSEC)
+			}
+
+			/// Enable or disable the `SO_PASSSEC` option.
+			///
+			/// When enabled, every received message carries the security context of the sender as ancillary data.
+			/// The security context is exposed as an [`AncillaryMessage::Other`](crate::ancillary::AncillaryMessage::Other) message with type `SCM_SECURITY`.
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn set_passsec(&self, enable: bool) -> std::io::Result<()> {
+				$crate::sys::set_bool_option(self.io.get_ref(), libc::SO_PASSSEC, enable)
+			}
+
+			/// Get the type of the socket (`SO_TYPE`).
+			///
+			#[doc = concat!("For a valid [`", stringify!($type), "`] this is always `SOCK_SEQPACKET`.")]
+			pub fn socket_type(&self) -> std::io::Result<std::os::raw::c_int> {
+				$crate::sys::get_int_option(self.io.get_ref(), libc::SOL_SOCKET, libc::SO_TYPE)
+			}
+
+			/// Get the domain of the socket (`SO_DOMAIN`).
+			///
+			#[doc = concat!("For a valid [`", stringify!($type), "`] this is always `AF_UNIX`.")]
+			#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+			pub fn socket_domain(&self) -> std::io::Result<std::os::raw::c_int> {
+				$crate::sys::get_int_option(self.io.get_ref(), libc::SOL_SOCKET, libc::SO_DOMAIN)
+			}
+
+			/// Check if the socket is listening for connections (`SO_ACCEPTCONN`).
+			pub fn is_listener(&self) -> std::io::Result<bool> {
+				$crate::sys::get_bool_option(self.io.get_ref(), libc::SO_ACCEPTCONN)
+			}
+		}
+	};
+}
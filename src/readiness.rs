@@ -0,0 +1,159 @@
+use filedesc::FileDesc;
+use std::io::{IoSlice, IoSliceMut};
+use std::os::raw::c_int;
+use std::task::{Context, Poll};
+
+use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
+use crate::{sys, MessageInfo};
+
+/// The direction of an I/O operation, which determines the readiness to wait for.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Direction {
+	/// Wait for the file descriptor to become readable.
+	Read,
+
+	/// Wait for the file descriptor to become writable.
+	Write,
+}
+
+/// Readiness notifications from an async reactor for a non-blocking file descriptor.
+///
+/// This is the only part of the I/O functions that differs between the async backends.
+/// The sockets and listeners of all backends run the system calls through this trait,
+/// and the non-trivial operations are implemented on top of it in this module.
+pub(crate) trait Readiness {
+	/// Try a non-blocking operation until it does not fail with [`std::io::ErrorKind::WouldBlock`].
+	///
+	/// If the operation would block, the current task is scheduled to wake up when the file descriptor becomes ready.
+	fn poll_io<T>(
+		&self,
+		cx: &mut Context,
+		direction: Direction,
+		f: impl FnMut(&FileDesc) -> std::io::Result<T>,
+	) -> Poll<std::io::Result<T>>;
+
+	/// Wait for the file descriptor to become ready, and run a non-blocking operation until it does not fail with [`std::io::ErrorKind::WouldBlock`].
+	async fn io<T>(&self, direction: Direction, f: impl FnMut(&FileDesc) -> std::io::Result<T>) -> std::io::Result<T>;
+}
+
+/// Try to send a message with ancillary data without blocking.
+pub(crate) fn poll_send_msg(
+	io: &impl Readiness,
+	cx: &mut Context,
+	buffer: &[IoSlice],
+	ancillary: &mut AncillaryMessageWriter,
+) -> Poll<std::io::Result<usize>> {
+	io.poll_io(cx, Direction::Write, |fd| sys::send_msg(fd, buffer, ancillary))
+}
+
+/// Send a message with ancillary data.
+pub(crate) async fn send_msg(
+	io: &impl Readiness,
+	buffer: &[IoSlice<'_>],
+	ancillary: &mut AncillaryMessageWriter<'_>,
+) -> std::io::Result<usize> {
+	io.io(Direction::Write, |fd| sys::send_msg(fd, buffer, ancillary)).await
+}
+
+/// Try to receive a message with ancillary data without blocking.
+///
+/// The `flags` are passed to `recvmsg()` in addition to the default flags.
+pub(crate) fn poll_recv_msg<'a>(
+	io: &impl Readiness,
+	cx: &mut Context,
+	buffer: &mut [IoSliceMut],
+	ancillary_buffer: &'a mut [u8],
+	flags: c_int,
+) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
+	let (info, ancillary_len) = ready!(io.poll_io(cx, Direction::Read, |fd| {
+		sys::recv_msg_raw(fd, buffer, ancillary_buffer, flags)
+	}))?;
+	// SAFETY: The buffer contains the ancillary data that we just received.
+	let ancillary = unsafe { sys::ancillary_reader(&mut ancillary_buffer[..ancillary_len], info.ancillary_truncated) };
+	Poll::Ready(Ok((info, ancillary)))
+}
+
+/// Receive a message with ancillary data.
+///
+/// The `flags` are passed to `recvmsg()` in addition to the default flags.
+pub(crate) async fn recv_msg<'a>(
+	io: &impl Readiness,
+	buffer: &mut [IoSliceMut<'_>],
+	ancillary_buffer: &'a mut [u8],
+	flags: c_int,
+) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+	let (info, ancillary_len) = io
+		.io(Direction::Read, |fd| {
+			sys::recv_msg_raw(fd, buffer, ancillary_buffer, flags)
+		})
+		.await?;
+	// SAFETY: The buffer contains the ancillary data that we just received.
+	let ancillary = unsafe { sys::ancillary_reader(&mut ancillary_buffer[..ancillary_len], info.ancillary_truncated) };
+	Ok((info, ancillary))
+}
+
+#[cfg(feature = "tokio")]
+impl Readiness for tokio::io::unix::AsyncFd<FileDesc> {
+	fn poll_io<T>(
+		&self,
+		cx: &mut Context,
+		direction: Direction,
+		mut f: impl FnMut(&FileDesc) -> std::io::Result<T>,
+	) -> Poll<std::io::Result<T>> {
+		loop {
+			let mut ready_guard = match direction {
+				Direction::Read => ready!(self.poll_read_ready(cx))?,
+				Direction::Write => ready!(self.poll_write_ready(cx))?,
+			};
+			match ready_guard.try_io(|inner| f(inner.get_ref())) {
+				Ok(result) => return Poll::Ready(result),
+				Err(_would_block) => continue,
+			}
+		}
+	}
+
+	async fn io<T>(
+		&self,
+		direction: Direction,
+		mut f: impl FnMut(&FileDesc) -> std::io::Result<T>,
+	) -> std::io::Result<T> {
+		loop {
+			let mut ready_guard = match direction {
+				Direction::Read => self.readable().await?,
+				Direction::Write => self.writable().await?,
+			};
+			match ready_guard.try_io(|inner| f(inner.get_ref())) {
+				Ok(result) => return result,
+				Err(_would_block) => continue,
+			}
+		}
+	}
+}
+
+#[cfg(feature = "async-io")]
+impl Readiness for async_io::Async<FileDesc> {
+	fn poll_io<T>(
+		&self,
+		cx: &mut Context,
+		direction: Direction,
+		mut f: impl FnMut(&FileDesc) -> std::io::Result<T>,
+	) -> Poll<std::io::Result<T>> {
+		loop {
+			match f(self.get_ref()) {
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => (),
+				result => return Poll::Ready(result),
+			}
+			match direction {
+				Direction::Read => ready!(self.poll_readable(cx))?,
+				Direction::Write => ready!(self.poll_writable(cx))?,
+			}
+		}
+	}
+
+	async fn io<T>(&self, direction: Direction, f: impl FnMut(&FileDesc) -> std::io::Result<T>) -> std::io::Result<T> {
+		match direction {
+			Direction::Read => self.read_with(f).await,
+			Direction::Write => self.write_with(f).await,
+		}
+	}
+}
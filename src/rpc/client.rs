@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::{FrameKind, Received, RpcError};
+use crate::{OutgoingMessage, ReceivedMessage, UnixSeqpacket};
+
+/// Client that sends requests and waits for the matching replies.
+///
+/// The client can be shared between tasks, and each task can have its own calls outstanding at the same time.
+/// Replies are received by a background task spawned on the current tokio runtime.
+/// The background task is aborted when the client is dropped.
+///
+/// See the [module documentation][super] for more information.
+pub struct RpcClient {
+	shared: Arc<Shared>,
+	reader: tokio::task::JoinHandle<()>,
+}
+
+/// Senders for the replies of outstanding calls, by correlation ID.
+type Pending = HashMap<u64, oneshot::Sender<Result<ReceivedMessage, RpcError>>>;
+
+/// State shared between the client and the background task receiving replies.
+struct Shared {
+	socket: UnixSeqpacket,
+	next_id: AtomicU64,
+
+	/// The outstanding calls, or `None` if the connection is closed.
+	pending: Mutex<Option<Pending>>,
+}
+
+impl std::fmt::Debug for RpcClient {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("RpcClient")
+			.field("socket", &self.shared.socket)
+			.finish_non_exhaustive()
+	}
+}
+
+impl RpcClient {
+	/// Create a new client for a connected socket.
+	///
+	/// This spawns the background task that receives replies,
+	/// so it must be called from within a tokio runtime.
+	pub fn new(socket: UnixSeqpacket) -> Self {
+		let shared = Arc::new(Shared {
+			socket,
+			next_id: AtomicU64::new(0),
+			pending: Mutex::new(Some(HashMap::new())),
+		});
+		let reader = tokio::spawn(read_replies(shared.clone()));
+		Self { shared, reader }
+	}
+
+	/// Send a request and wait for the reply.
+	///
+	/// If the returned future is dropped after the request was sent,
+	/// a cancellation notice is sent to the server.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	pub async fn call(&self, request: OutgoingMessage) -> Result<ReceivedMessage, RpcError> {
+		let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+		let (reply_tx, reply_rx) = oneshot::channel();
+		match self.shared.lock_pending().as_mut() {
+			Some(pending) => pending.insert(id, reply_tx),
+			None => return Err(RpcError::Disconnected),
+		};
+
+		let mut guard = CallGuard {
+			shared: &self.shared,
+			id,
+			sent: false,
+			done: false,
+		};
+		super::send_frame(&self.shared.socket, FrameKind::Request, id, &request).await?;
+		guard.sent = true;
+
+		let reply = reply_rx.await.map_err(|_| RpcError::Disconnected);
+		guard.done = true;
+		reply?
+	}
+
+	/// Send a request and wait for the reply with a timeout.
+	///
+	/// If no reply is received before the timeout expires, a cancellation notice is sent to the server
+	/// and [`RpcError::TimedOut`] is returned.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn call_timeout(&self, request: OutgoingMessage, timeout: Duration) -> Result<ReceivedMessage, RpcError> {
+		tokio::time::timeout(timeout, self.call(request))
+			.await
+			.map_err(|_elapsed| RpcError::TimedOut)?
+	}
+}
+
+impl Drop for RpcClient {
+	fn drop(&mut self) {
+		self.reader.abort();
+	}
+}
+
+impl Shared {
+	fn lock_pending(&self) -> MutexGuard<'_, Option<Pending>> {
+		self.pending.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+/// Cleans up after a call, and sends a cancellation notice if the call is dropped before the reply is received.
+struct CallGuard<'a> {
+	shared: &'a Arc<Shared>,
+	id: u64,
+	sent: bool,
+	done: bool,
+}
+
+impl Drop for CallGuard<'_> {
+	fn drop(&mut self) {
+		if self.done {
+			return;
+		}
+		if let Some(pending) = self.shared.lock_pending().as_mut() {
+			pending.remove(&self.id);
+		}
+		if !self.sent {
+			return;
+		}
+
+		// Cancellation notices are best effort: if the socket is not writable right now, send it from a new task.
+		match super::try_send_notice(&self.shared.socket, FrameKind::Cancel, self.id) {
+			Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+				if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+					let shared = self.shared.clone();
+					let header = super::header(FrameKind::Cancel, self.id);
+					runtime.spawn(async move { shared.socket.send(&header).await });
+				}
+			},
+			_ => (),
+		}
+	}
+}
+
+/// Receive replies and pass them to the waiting calls until the connection is closed.
+async fn read_replies(shared: Arc<Shared>) {
+	let mut ancillary_buffer = super::ancillary_buffer();
+	loop {
+		let frame = match super::recv_frame(&shared.socket, &mut ancillary_buffer).await {
+			Ok(Received::Frame(frame)) => frame,
+			Ok(Received::Invalid) => continue,
+			Ok(Received::Closed) | Err(_) => break,
+		};
+		let reply = match frame.kind {
+			FrameKind::Reply => Ok(frame.message),
+			FrameKind::Error => Err(RpcError::Remote(
+				String::from_utf8_lossy(&frame.message.data).into_owned(),
+			)),
+			FrameKind::Request | FrameKind::Cancel => continue,
+		};
+		let reply_tx = shared
+			.lock_pending()
+			.as_mut()
+			.and_then(|pending| pending.remove(&frame.id));
+		// If the call was cancelled, the reply is dropped, which closes its file descriptors.
+		if let Some(reply_tx) = reply_tx {
+			reply_tx.send(reply).ok();
+		}
+	}
+
+	// Wake up all outstanding calls with `RpcError::Disconnected`.
+	shared.lock_pending().take();
+}
@@ -0,0 +1,244 @@
+//! Request/response RPC over seqpacket sockets.
+//!
+//! An [`RpcClient`] sends requests over a [`UnixSeqpacket`] and waits for the matching replies.
+//! Many tasks can share a single client and have calls outstanding at the same time:
+//! each request carries a correlation ID, and the replies are routed back to the right caller.
+//! On the other side of the connection, [`serve()`] dispatches each request to a handler running in its own task.
+//!
+//! Requests and replies are [`OutgoingMessage`]s when sent and [`ReceivedMessage`]s when received,
+//! so they can carry file descriptors as ancillary data, just like regular messages.
+//!
+//! A call can be given a timeout with [`RpcClient::call_timeout()`].
+//! When a call times out or its future is dropped, a cancellation notice is sent to the server,
+//! which aborts the handler for the request if it is still running.
+//!
+//! # Wire format
+//!
+//! Each request, reply or notice is a single seqpacket message.
+//! It starts with a header of 9 bytes, followed by the payload:
+//! * 1 byte for the kind of message: 1 for a request, 2 for a reply, 3 for an error reply and 4 for a cancellation notice.
+//! * 8 bytes for the correlation ID in little-endian byte order.
+//!
+//! The payload of an error reply is the error message encoded as UTF-8.
+//! Cancellation notices have no payload.
+//! Messages that are not valid RPC messages are ignored by both the client and the server.
+//!
+//! # Example
+//! ```no_run
+//! # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+//! use tokio_seqpacket::rpc::{self, RpcClient};
+//! use tokio_seqpacket::{OutgoingMessage, UnixSeqpacket};
+//!
+//! let (client, server) = UnixSeqpacket::pair()?;
+//! tokio::spawn(rpc::serve(server, |request| async move {
+//!     Ok(OutgoingMessage::new(request.data))
+//! }));
+//!
+//! let client = RpcClient::new(client);
+//! let reply = client.call(OutgoingMessage::new("Hello!")).await?;
+//! assert_eq!(reply.data, b"Hello!");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::IoSlice;
+
+use crate::{OutgoingMessage, ReceivedMessage, UnixSeqpacket};
+
+mod client;
+mod server;
+
+pub use client::RpcClient;
+pub use server::serve;
+#[cfg(feature = "tower")]
+pub(crate) use server::{serve_with, BoxHandlerFuture, Dispatch};
+
+/// The size of the header of each message.
+const HEADER_LEN: usize = 9;
+
+/// The maximum number of file descriptors that can be received with a single message.
+///
+/// This is the limit imposed by Linux (`SCM_MAX_FD`).
+const MAX_FDS: usize = 253;
+
+/// The kind of an RPC message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum FrameKind {
+	Request = 1,
+	Reply = 2,
+	Error = 3,
+	Cancel = 4,
+}
+
+impl FrameKind {
+	fn from_u8(value: u8) -> Option<Self> {
+		match value {
+			1 => Some(Self::Request),
+			2 => Some(Self::Reply),
+			3 => Some(Self::Error),
+			4 => Some(Self::Cancel),
+			_ => None,
+		}
+	}
+}
+
+/// A received RPC message.
+struct Frame {
+	kind: FrameKind,
+	id: u64,
+	message: ReceivedMessage,
+}
+
+/// The result of receiving a message on an RPC connection.
+enum Received {
+	/// A valid RPC message.
+	Frame(Frame),
+
+	/// A message that is not a valid RPC message.
+	///
+	/// The file descriptors of the message are already closed.
+	Invalid,
+
+	/// The peer closed the connection.
+	Closed,
+}
+
+/// Error for an RPC call.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RpcError {
+	/// Sending the request failed.
+	Io(std::io::Error),
+
+	/// No reply was received within the timeout.
+	TimedOut,
+
+	/// The connection was closed before a reply was received.
+	Disconnected,
+
+	/// The request handler returned an error.
+	Remote(String),
+}
+
+impl std::fmt::Display for RpcError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Io(e) => write!(f, "{e}"),
+			Self::TimedOut => write!(f, "timed out waiting for reply"),
+			Self::Disconnected => write!(f, "connection closed before a reply was received"),
+			Self::Remote(message) => write!(f, "request failed: {message}"),
+		}
+	}
+}
+
+impl std::error::Error for RpcError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io(e) => Some(e),
+			Self::TimedOut => None,
+			Self::Disconnected => None,
+			Self::Remote(_) => None,
+		}
+	}
+}
+
+impl From<std::io::Error> for RpcError {
+	fn from(other: std::io::Error) -> Self {
+		Self::Io(other)
+	}
+}
+
+impl From<RpcError> for std::io::Error {
+	fn from(other: RpcError) -> Self {
+		match other {
+			RpcError::Io(e) => e,
+			RpcError::TimedOut => std::io::ErrorKind::TimedOut.into(),
+			RpcError::Disconnected => std::io::ErrorKind::ConnectionReset.into(),
+			other => std::io::Error::other(other),
+		}
+	}
+}
+
+/// Encode the header of an RPC message.
+fn header(kind: FrameKind, id: u64) -> [u8; HEADER_LEN] {
+	let mut header = [0; HEADER_LEN];
+	header[0] = kind as u8;
+	header[1..].copy_from_slice(&id.to_le_bytes());
+	header
+}
+
+/// Send an RPC message with a payload and file descriptors.
+async fn send_frame(
+	socket: &UnixSeqpacket,
+	kind: FrameKind,
+	id: u64,
+	message: &OutgoingMessage,
+) -> std::io::Result<()> {
+	let header = header(kind, id);
+	let mut ancillary_buffer = vec![0; message.ancillary_buffer_len()?];
+	let mut ancillary = message.ancillary_writer(&mut ancillary_buffer)?;
+	socket
+		.send_vectored_with_ancillary(&[IoSlice::new(&header), IoSlice::new(&message.data)], &mut ancillary)
+		.await?;
+	Ok(())
+}
+
+/// Try to send an RPC message without payload without blocking.
+fn try_send_notice(socket: &UnixSeqpacket, kind: FrameKind, id: u64) -> std::io::Result<()> {
+	socket.try_send(&header(kind, id))?;
+	Ok(())
+}
+
+/// Create an ancillary buffer large enough for the file descriptors of any message.
+fn ancillary_buffer() -> Vec<u8> {
+	// SAFETY: CMSG_SPACE only does arithmetic on its argument.
+	let space = unsafe { libc::CMSG_SPACE((MAX_FDS * std::mem::size_of::<std::os::fd::RawFd>()) as u32) } as usize;
+	vec![0; space + crate::ancillary::AncillaryMessageWriter::BUFFER_ALIGN]
+}
+
+/// Get the size of the buffer to receive the next RPC message in.
+///
+/// On Linux and Android, we can peek at the size of the next message without touching its ancillary data.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+async fn frame_buffer_len(socket: &UnixSeqpacket) -> std::io::Result<usize> {
+	socket.next_message_len().await
+}
+
+/// Get the size of the buffer to receive the next RPC message in.
+///
+/// On other platforms, peeking at the size of the next message may discard the file descriptors attached to it.
+/// A message can not be larger than the receive buffer of the socket, so we use a buffer of that size instead.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+async fn frame_buffer_len(socket: &UnixSeqpacket) -> std::io::Result<usize> {
+	socket.recv_buffer_size()
+}
+
+/// Receive an RPC message.
+///
+/// Messages that are truncated, shorter than the header or of an unknown kind are reported as [`Received::Invalid`],
+/// so the caller can skip them without closing the connection.
+/// Only real I/O errors are returned as errors.
+async fn recv_frame(socket: &UnixSeqpacket, ancillary_buffer: &mut [u8]) -> std::io::Result<Received> {
+	let mut data = vec![0; frame_buffer_len(socket).await?];
+	let (info, ancillary) = socket.recv_with_ancillary(&mut data, ancillary_buffer).await?;
+	// Take ownership of the file descriptors first, so they are closed if the message is invalid.
+	let mut message = ReceivedMessage::from_parts(data, ancillary);
+
+	// RPC messages are never empty, so this is the end of the connection.
+	if info.bytes_read == 0 {
+		return Ok(Received::Closed);
+	}
+	if info.truncated || info.ancillary_truncated {
+		return Ok(Received::Invalid);
+	}
+	message.data.truncate(info.bytes_read);
+	if message.data.len() < HEADER_LEN {
+		return Ok(Received::Invalid);
+	}
+	let Some(kind) = FrameKind::from_u8(message.data[0]) else {
+		return Ok(Received::Invalid);
+	};
+	let id = u64::from_le_bytes(message.data[1..HEADER_LEN].try_into().unwrap());
+	message.data.drain(..HEADER_LEN);
+	Ok(Received::Frame(Frame { kind, id, message }))
+}
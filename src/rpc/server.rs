@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use tokio::task::{AbortHandle, JoinSet};
+
+use super::{FrameKind, Received};
+use crate::{OutgoingMessage, ReceivedMessage, UnixSeqpacket};
+
+/// The handlers that are currently running, by correlation ID.
+type Running = Arc<Mutex<HashMap<u64, AbortHandle>>>;
+
+/// Boxed future of a request handler.
+pub(crate) type BoxHandlerFuture = Pin<Box<dyn Future<Output = std::io::Result<OutgoingMessage>> + Send>>;
+
+/// Passes requests to their handlers.
+pub(crate) trait Dispatch {
+	/// Wait until the next request can be dispatched.
+	///
+	/// No further requests are received until this is ready.
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>>;
+
+	/// Dispatch a request and return the future to handle it.
+	///
+	/// The future is run in a separate task.
+	fn dispatch(&mut self, request: ReceivedMessage) -> BoxHandlerFuture;
+}
+
+/// Dispatches requests to a handler function, which is always ready.
+struct HandlerFn<H>(Arc<H>);
+
+impl<H, F> Dispatch for HandlerFn<H>
+where
+	H: Fn(ReceivedMessage) -> F + Send + Sync + 'static,
+	F: Future<Output = std::io::Result<OutgoingMessage>> + Send + 'static,
+{
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn dispatch(&mut self, request: ReceivedMessage) -> BoxHandlerFuture {
+		// Call the handler in the spawned task, so a panic results in an error reply.
+		let handler = self.0.clone();
+		Box::pin(async move { handler(request).await })
+	}
+}
+
+/// Serve requests on a connected socket until the peer closes the connection.
+///
+/// Each request is passed to the handler in a separate task spawned on the current tokio runtime,
+/// so multiple requests can be handled concurrently.
+/// The reply returned by the handler is sent back with the correlation ID of the request.
+/// If the handler returns an error, the error message is sent back instead,
+/// and the call fails with [`RpcError::Remote`][super::RpcError::Remote] on the client.
+///
+/// When a cancellation notice is received for a request, the handler for that request is aborted.
+/// Messages that are not valid RPC messages are ignored.
+///
+/// When the peer closes the connection, any running handlers are aborted and this function returns `Ok(())`.
+/// If receiving a request fails, any running handlers are aborted and the error is returned.
+///
+/// This function must be called from within a tokio runtime.
+pub async fn serve<H, F>(socket: UnixSeqpacket, handler: H) -> std::io::Result<()>
+where
+	H: Fn(ReceivedMessage) -> F + Send + Sync + 'static,
+	F: Future<Output = std::io::Result<OutgoingMessage>> + Send + 'static,
+{
+	serve_with(socket, HandlerFn(Arc::new(handler))).await
+}
+
+/// Serve requests on a connected socket with the given dispatcher until the peer closes the connection.
+///
+/// Before each request is dispatched, this waits for the dispatcher to become ready.
+/// If that fails, any running handlers are aborted and the error is returned.
+pub(crate) async fn serve_with(socket: UnixSeqpacket, mut dispatcher: impl Dispatch) -> std::io::Result<()> {
+	let socket = Arc::new(socket);
+	let running = Running::default();
+	let mut tasks = JoinSet::new();
+	let mut ancillary_buffer = super::ancillary_buffer();
+
+	let result = loop {
+		let frame = match super::recv_frame(&socket, &mut ancillary_buffer).await {
+			Ok(Received::Frame(frame)) => frame,
+			Ok(Received::Invalid) => continue,
+			Ok(Received::Closed) => break Ok(()),
+			Err(e) => break Err(e),
+		};
+
+		// Clean up finished handlers.
+		while tasks.try_join_next().is_some() {}
+
+		match frame.kind {
+			FrameKind::Request => {
+				if let Err(e) = std::future::poll_fn(|cx| dispatcher.poll_ready(cx)).await {
+					break Err(e);
+				}
+				let reply = dispatcher.dispatch(frame.message);
+
+				// Keep the lock while spawning, so the handler can not remove itself before it is added.
+				let mut running_lock = lock(&running);
+				let task = tasks.spawn(handle_request(socket.clone(), running.clone(), frame.id, reply));
+				if let Some(previous) = running_lock.insert(frame.id, task) {
+					// The client reused a correlation ID, so it is no longer interested in the previous request.
+					previous.abort();
+				}
+			},
+			FrameKind::Cancel => {
+				if let Some(task) = lock(&running).remove(&frame.id) {
+					task.abort();
+				}
+			},
+			FrameKind::Reply | FrameKind::Error => (),
+		}
+	};
+
+	tasks.abort_all();
+	result
+}
+
+/// Run the handler for a single request and send the reply.
+async fn handle_request(socket: Arc<UnixSeqpacket>, running: Running, id: u64, reply: BoxHandlerFuture) {
+	let guard = RunningGuard {
+		socket: &socket,
+		running: &running,
+		id,
+	};
+	let reply = reply.await;
+	drop(guard);
+
+	// If sending the reply fails, the connection is broken, and receiving the next request will report the error.
+	match reply {
+		Ok(reply) => super::send_frame(&socket, FrameKind::Reply, id, &reply).await.ok(),
+		Err(e) => super::send_frame(&socket, FrameKind::Error, id, &OutgoingMessage::new(e.to_string()))
+			.await
+			.ok(),
+	};
+}
+
+/// Removes a handler from the running handlers when it finishes, panics or is aborted.
+///
+/// If the handler panics, an error reply is sent, so the client does not wait forever.
+struct RunningGuard<'a> {
+	socket: &'a UnixSeqpacket,
+	running: &'a Running,
+	id: u64,
+}
+
+impl Drop for RunningGuard<'_> {
+	fn drop(&mut self) {
+		{
+			// The client may have reused the correlation ID for a new request, so only remove our own entry.
+			let mut running = lock(self.running);
+			let ours = running
+				.get(&self.id)
+				.is_some_and(|task| Some(task.id()) == tokio::task::try_id());
+			if ours {
+				running.remove(&self.id);
+			}
+		}
+
+		if std::thread::panicking() {
+			let header = super::header(FrameKind::Error, self.id);
+			let mut message = header.to_vec();
+			message.extend_from_slice(b"request handler panicked");
+			self.socket.try_send(&message).ok();
+		}
+	}
+}
+
+fn lock(running: &Running) -> MutexGuard<'_, HashMap<u64, AbortHandle>> {
+	running.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,300 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::{UCred, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Server that accepts connections on a listener and runs a handler for each of them.
+///
+/// Each connection is handled in a separate task spawned on the current tokio runtime.
+/// The server can limit the number of concurrent connections,
+/// and it can drop connections that do not send anything within a handshake timeout.
+///
+/// When the shutdown signal passed to [`Self::run()`] completes, the server stops accepting new connections.
+/// It then waits for the running connection handlers to finish, optionally limited by a grace period.
+///
+/// Errors for individual connections do not stop the server.
+/// Instead, they are reported to the callback set with [`Self::on_error()`].
+///
+/// # Example
+/// ```no_run
+/// # async fn foo() -> std::io::Result<()> {
+/// use std::time::Duration;
+/// use tokio_seqpacket::{Server, UnixSeqpacketListener};
+///
+/// let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+/// let listener = UnixSeqpacketListener::bind("/run/foo.sock")?;
+/// let server = Server::new(listener, |socket, credentials| async move {
+///     let mut buffer = [0; 1024];
+///     let msg_info = socket.recv(&mut buffer).await?;
+///     socket.send(&buffer[..msg_info.bytes_read()]).await?;
+///     Ok(())
+/// })
+/// .max_connections(64)
+/// .handshake_timeout(Duration::from_secs(5))
+/// .grace_period(Duration::from_secs(10))
+/// .on_error(|error| eprintln!("{error}"));
+///
+/// // Call `shutdown_tx.send(())` to stop the server.
+/// server.run(async { shutdown_rx.await.ok(); }).await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Server<H> {
+	listener: UnixSeqpacketListener,
+	handler: Arc<H>,
+	max_connections: Option<usize>,
+	handshake_timeout: Option<Duration>,
+	grace_period: Option<Duration>,
+	on_error: Arc<dyn Fn(ConnectionError) + Send + Sync>,
+}
+
+impl<H> std::fmt::Debug for Server<H> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Server")
+			.field("listener", &self.listener)
+			.field("max_connections", &self.max_connections)
+			.field("handshake_timeout", &self.handshake_timeout)
+			.field("grace_period", &self.grace_period)
+			.finish_non_exhaustive()
+	}
+}
+
+/// Error for a single connection of a [`Server`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConnectionError {
+	/// Accepting a new connection failed.
+	Accept(std::io::Error),
+
+	/// Retrieving the credentials of the peer failed.
+	Credentials(std::io::Error),
+
+	/// The peer did not send anything within the handshake timeout.
+	HandshakeTimeout(UCred),
+
+	/// Waiting for the handshake failed.
+	Handshake(UCred, std::io::Error),
+
+	/// The connection handler returned an error.
+	Handler(UCred, std::io::Error),
+
+	/// The connection handler panicked.
+	Panicked,
+
+	/// The connection handler was aborted because it did not finish within the grace period.
+	Aborted,
+}
+
+impl std::fmt::Display for ConnectionError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Accept(e) => write!(f, "failed to accept connection: {e}"),
+			Self::Credentials(e) => write!(f, "failed to get peer credentials: {e}"),
+			Self::HandshakeTimeout(peer) => write!(f, "connection from {peer:?} timed out during handshake"),
+			Self::Handshake(peer, e) => write!(f, "handshake with {peer:?} failed: {e}"),
+			Self::Handler(peer, e) => write!(f, "error in connection with {peer:?}: {e}"),
+			Self::Panicked => write!(f, "connection handler panicked"),
+			Self::Aborted => write!(f, "connection handler aborted after grace period"),
+		}
+	}
+}
+
+impl std::error::Error for ConnectionError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Accept(e) => Some(e),
+			Self::Credentials(e) => Some(e),
+			Self::HandshakeTimeout(_) => None,
+			Self::Handshake(_, e) => Some(e),
+			Self::Handler(_, e) => Some(e),
+			Self::Panicked => None,
+			Self::Aborted => None,
+		}
+	}
+}
+
+impl<H, F> Server<H>
+where
+	H: Fn(UnixSeqpacket, UCred) -> F + Send + Sync + 'static,
+	F: Future<Output = std::io::Result<()>> + Send + 'static,
+{
+	/// Create a new server for a listener and a connection handler.
+	///
+	/// The handler is called for each accepted connection with the socket and the credentials of the peer.
+	pub fn new(listener: UnixSeqpacketListener, handler: H) -> Self {
+		Self {
+			listener,
+			handler: Arc::new(handler),
+			max_connections: None,
+			handshake_timeout: None,
+			grace_period: None,
+			on_error: Arc::new(|_| ()),
+		}
+	}
+
+	/// Set the maximum number of concurrent connections.
+	///
+	/// When the limit is reached, the server stops accepting new connections until a connection handler finishes.
+	/// Pending connections wait in the backlog of the listener.
+	///
+	/// By default, the number of connections is not limited.
+	///
+	/// # Panics
+	/// This function panics if `max_connections` is zero, since the server could never accept a connection.
+	pub fn max_connections(mut self, max_connections: usize) -> Self {
+		assert!(max_connections > 0, "max_connections must be at least 1");
+		self.max_connections = Some(max_connections);
+		self
+	}
+
+	/// Set the handshake timeout.
+	///
+	/// When set, the handler is only called after the peer sent the first message or closed the connection.
+	/// If that doesn't happen within the timeout, the connection is dropped and a [`ConnectionError::HandshakeTimeout`] is reported.
+	/// This prevents idle clients from occupying connection slots.
+	///
+	/// By default, the handler is called immediately.
+	pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+		self.handshake_timeout = Some(timeout);
+		self
+	}
+
+	/// Set the grace period for running connection handlers after the shutdown signal.
+	///
+	/// Connection handlers that are still running when the grace period expires are aborted.
+	///
+	/// By default, the server waits for all connection handlers to finish.
+	pub fn grace_period(mut self, grace_period: Duration) -> Self {
+		self.grace_period = Some(grace_period);
+		self
+	}
+
+	/// Set the callback for connection errors.
+	///
+	/// By default, connection errors are ignored.
+	pub fn on_error<E>(mut self, on_error: E) -> Self
+	where
+		E: Fn(ConnectionError) + Send + Sync + 'static,
+	{
+		self.on_error = Arc::new(on_error);
+		self
+	}
+
+	/// Run the server until the shutdown signal completes.
+	///
+	/// After the shutdown signal completes, no new connections are accepted.
+	/// This function then waits for the running connection handlers to finish, or for the grace period to expire.
+	///
+	/// This function must be called from within a tokio runtime.
+	pub async fn run<S: Future<Output = ()>>(mut self, shutdown: S) {
+		let semaphore = Arc::new(Semaphore::new(self.max_connections.unwrap_or(Semaphore::MAX_PERMITS)));
+		let mut tasks = JoinSet::new();
+		let mut shutdown = std::pin::pin!(shutdown);
+
+		loop {
+			let permit = tokio::select! {
+				biased;
+				_ = &mut shutdown => break,
+				Some(result) = tasks.join_next(), if !tasks.is_empty() => {
+					self.report_join_result(result);
+					continue;
+				},
+				permit = semaphore.clone().acquire_owned() => permit.expect("semaphore is never closed"),
+			};
+
+			let socket = tokio::select! {
+				biased;
+				_ = &mut shutdown => break,
+				Some(result) = tasks.join_next(), if !tasks.is_empty() => {
+					self.report_join_result(result);
+					continue;
+				},
+				socket = self.listener.accept() => socket,
+			};
+
+			let socket = match socket {
+				Ok(socket) => socket,
+				Err(e) => {
+					(self.on_error)(ConnectionError::Accept(e));
+					// Avoid a busy loop when accepting keeps failing, for example when we ran out of file descriptors.
+					// But don't delay the shutdown while waiting.
+					tokio::select! {
+						biased;
+						_ = &mut shutdown => break,
+						_ = tokio::time::sleep(Duration::from_millis(100)) => continue,
+					}
+				},
+			};
+
+			let handler = self.handler.clone();
+			let handshake_timeout = self.handshake_timeout;
+			tasks.spawn(async move {
+				let _permit = permit;
+				handle_connection(socket, handler, handshake_timeout).await
+			});
+		}
+
+		// Stop accepting connections before draining the running connection handlers.
+		drop(self.listener);
+		let drain = async {
+			while let Some(result) = tasks.join_next().await {
+				report_join_result(&*self.on_error, result);
+			}
+		};
+
+		match self.grace_period {
+			None => drain.await,
+			Some(grace_period) => {
+				if tokio::time::timeout(grace_period, drain).await.is_err() {
+					tasks.abort_all();
+					while let Some(result) = tasks.join_next().await {
+						report_join_result(&*self.on_error, result);
+					}
+				}
+			},
+		}
+	}
+
+	fn report_join_result(&self, result: Result<Result<(), ConnectionError>, tokio::task::JoinError>) {
+		report_join_result(&*self.on_error, result)
+	}
+}
+
+/// Run the handshake and the handler for a single connection.
+async fn handle_connection<H, F>(
+	socket: UnixSeqpacket,
+	handler: Arc<H>,
+	handshake_timeout: Option<Duration>,
+) -> Result<(), ConnectionError>
+where
+	H: Fn(UnixSeqpacket, UCred) -> F,
+	F: Future<Output = std::io::Result<()>>,
+{
+	let credentials = socket.peer_cred().map_err(ConnectionError::Credentials)?;
+	if let Some(timeout) = handshake_timeout {
+		match tokio::time::timeout(timeout, socket.as_async_fd().readable()).await {
+			Ok(Ok(mut guard)) => guard.retain_ready(),
+			Ok(Err(e)) => return Err(ConnectionError::Handshake(credentials, e)),
+			Err(_elapsed) => return Err(ConnectionError::HandshakeTimeout(credentials)),
+		}
+	}
+	handler(socket, credentials)
+		.await
+		.map_err(|e| ConnectionError::Handler(credentials, e))
+}
+
+/// Report the result of a connection task to the error callback.
+fn report_join_result(
+	on_error: &(dyn Fn(ConnectionError) + Send + Sync),
+	result: Result<Result<(), ConnectionError>, tokio::task::JoinError>,
+) {
+	match result {
+		Ok(Ok(())) => (),
+		Ok(Err(e)) => on_error(e),
+		Err(e) if e.is_panic() => on_error(ConnectionError::Panicked),
+		Err(_cancelled) => on_error(ConnectionError::Aborted),
+	}
+}
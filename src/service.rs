@@ -0,0 +1,141 @@
+//! Integration with [`tower`](https://docs.rs/tower) services.
+//!
+//! [`SeqpacketClient`] implements [`Service`] on top of an [`RpcClient`],
+//! so tower middleware like timeouts, retries and rate limits can be used for calls over a seqpacket socket.
+//!
+//! On the server side, [`serve_connection()`] drives a [`Service`] for a single connection,
+//! and [`serve()`] does the same for each connection accepted from a [`UnixSeqpacketListener`].
+//! To limit the number of connections or to shut down gracefully, use [`serve_connection()`] as the connection handler of a `Server` from the `server` feature.
+//!
+//! Requests are sent as [`OutgoingMessage`] and received as [`ReceivedMessage`], and the same goes for responses.
+//! Both can carry file descriptors.
+//! The messages are exchanged with the protocol of the [`rpc`][crate::rpc] module.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+use crate::rpc::{RpcClient, RpcError};
+use crate::{OutgoingMessage, ReceivedMessage, ToSocketAddr, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Boxed error type of a service.
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Client that implements [`Service`] for calls over a seqpacket socket.
+///
+/// The client can be cloned cheaply: all clones share the same connection.
+/// Each clone can have its own calls outstanding at the same time.
+#[derive(Debug, Clone)]
+pub struct SeqpacketClient {
+	client: Arc<RpcClient>,
+}
+
+impl SeqpacketClient {
+	/// Create a new client for a connected socket.
+	///
+	/// This spawns a background task that receives responses,
+	/// so it must be called from within a tokio runtime.
+	pub fn new(socket: UnixSeqpacket) -> Self {
+		Self::from(RpcClient::new(socket))
+	}
+
+	/// Connect to a server at the given address.
+	///
+	/// This must be called from within a tokio runtime.
+	pub async fn connect<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		Ok(Self::new(UnixSeqpacket::connect(address).await?))
+	}
+}
+
+impl From<RpcClient> for SeqpacketClient {
+	fn from(client: RpcClient) -> Self {
+		Self {
+			client: Arc::new(client),
+		}
+	}
+}
+
+impl Service<OutgoingMessage> for SeqpacketClient {
+	type Response = ReceivedMessage;
+	type Error = RpcError;
+	type Future = Pin<Box<dyn Future<Output = Result<ReceivedMessage, RpcError>> + Send>>;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), RpcError>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, request: OutgoingMessage) -> Self::Future {
+		let client = self.client.clone();
+		Box::pin(async move { client.call(request).await })
+	}
+}
+
+/// Drive a service for the requests received on a single connection.
+///
+/// The service is polled for readiness before each request is passed to it,
+/// and no further requests are received while the service is not ready.
+/// The future returned by the service runs in a separate task, so multiple requests can be handled concurrently.
+/// If the service returns an error, the error message is sent back instead of a response,
+/// and the call fails with [`RpcError::Remote`] on the client.
+///
+/// This function returns when the peer closes the connection.
+/// If the service fails to become ready, the connection is closed and the error is returned.
+/// See [`rpc::serve()`][crate::rpc::serve] for more information.
+///
+/// This function must be called from within a tokio runtime.
+pub async fn serve_connection<S>(socket: UnixSeqpacket, service: S) -> std::io::Result<()>
+where
+	S: Service<ReceivedMessage, Response = OutgoingMessage> + Send + 'static,
+	S::Error: Into<BoxError>,
+	S::Future: Send + 'static,
+{
+	crate::rpc::serve_with(socket, ServiceDispatch(service)).await
+}
+
+/// Accept connections on a listener and drive a clone of the service for each of them.
+///
+/// Each connection is served in a separate task with [`serve_connection()`].
+/// Errors of individual connections are discarded: the connection is closed without affecting the other connections.
+///
+/// This function only returns if accepting a connection fails.
+/// The first accept error is returned, and no more connections are accepted,
+/// but the connections that are already being served keep running.
+/// Use a `Server` from the `server` feature to retry after accept errors or to shut down gracefully.
+///
+/// This function must be called from within a tokio runtime.
+pub async fn serve<S>(mut listener: UnixSeqpacketListener, service: S) -> std::io::Result<()>
+where
+	S: Service<ReceivedMessage, Response = OutgoingMessage> + Clone + Send + 'static,
+	S::Error: Into<BoxError>,
+	S::Future: Send + 'static,
+{
+	loop {
+		let socket = listener.accept().await?;
+		tokio::spawn(serve_connection(socket, service.clone()));
+	}
+}
+
+/// Dispatches the requests of a connection to a service.
+struct ServiceDispatch<S>(S);
+
+impl<S> crate::rpc::Dispatch for ServiceDispatch<S>
+where
+	S: Service<ReceivedMessage, Response = OutgoingMessage>,
+	S::Error: Into<BoxError>,
+	S::Future: Send + 'static,
+{
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+		self.0.poll_ready(cx).map_err(into_io_error)
+	}
+
+	fn dispatch(&mut self, request: ReceivedMessage) -> crate::rpc::BoxHandlerFuture {
+		let response = self.0.call(request);
+		Box::pin(async move { response.await.map_err(into_io_error) })
+	}
+}
+
+fn into_io_error(error: impl Into<BoxError>) -> std::io::Error {
+	std::io::Error::other(error.into())
+}
@@ -1,60 +1,26 @@
 use filedesc::FileDesc;
 use std::io::{IoSlice, IoSliceMut};
+use std::os::raw::c_int;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, IntoRawFd, OwnedFd};
-use std::path::Path;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::io::unix::AsyncFd;
+use tokio::io::{Interest, Ready};
 
 use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
-use crate::{sys, UCred};
-
-/// Information about a received seqpacket message.
-#[derive(Debug, Clone)]
-pub struct MessageInfo {
-	pub(crate) bytes_read: usize,
-	pub(crate) truncated: bool,
-	pub(crate) ancillary_truncated: bool,
-}
+use crate::readiness::{self, Direction, Readiness};
+use crate::{sys, MessageInfo, OutgoingMessage, SocketAddr, ToSocketAddr, UCred, UnixSeqpacketBuilder};
 
-impl MessageInfo {
-	/// Get the number of data bytes that were read.
-	///
-	/// A value of zero either indicates an empty seqpacket message or that the sender closed
-	/// their half of the connection and all messages have been received.
-	pub fn bytes_read(&self) -> usize {
-		self.bytes_read
-	}
-
-	/// Check if the message was truncated due to insufficient buffer space.
-	///
-	/// The truncated bytes of a received message are lost forever.
-	/// As such, you should ensure that your buffer is large enough for the expected messages,
-	/// or [peek] at the message first to dynamically resize the receive buffer if necessary.
-	/// But keep in mind that peeking at a message may consume the ancillary data, making it impossible to retrieve later.
-	///
-	/// [peek]: UnixSeqpacket::peek
-	pub fn truncated(&self) -> bool {
-		self.truncated
-	}
-
-	/// Check if the ancillary data was truncated due to insufficient buffer space.
-	///
-	/// The truncated ancillary data of a received message is lost forever.
-	/// As such, you should ensure that your ancillary buffer is large enough for the expected messages.
-	/// Alternatively, if your platform supports it (like Linux and Android),
-	/// you can [peek] at the message first to dynamically resize the ancillary buffer as necessary.
-	///
-	/// [peek]: UnixSeqpacket::peek_with_ancillary
-	pub fn ancillary_truncated(&self) -> bool {
-		self.ancillary_truncated
-	}
-}
+/// Maximum number of chunks of a [`bytes::Buf`] to pass to the kernel in a single I/O vector.
+#[cfg(feature = "bytes")]
+const MAX_BUF_CHUNKS: usize = 64;
 
 /// Unix seqpacket socket.
 ///
-/// Note that there are no functions to get the local or remote address of the connection.
-/// That is because connected Unix sockets are always anonymous,
-/// which means that the address contains no useful information.
+/// The local and remote address of the connection are available through [`Self::local_addr()`] and [`Self::peer_addr()`].
+/// Note that a connected socket is often unnamed: a client socket is normally not bound to an address,
+/// so the accepting side usually sees an unnamed peer address.
+/// The client side does see the address of the listener it connected to.
 ///
 /// ## `peek` Methods and Ancillary Data
 ///
@@ -115,46 +81,36 @@ impl UnixSeqpacket {
 	}
 
 	/// Connect a new seqpacket socket to the given address.
-	pub async fn connect<P: AsRef<Path>>(address: P) -> std::io::Result<Self> {
-		let socket = sys::local_seqpacket_socket_non_blocking()?;
-		if let Err(e) = sys::connect(&socket, address) {
-			if e.kind() != std::io::ErrorKind::WouldBlock {
-				return Err(e);
-			}
-		}
+	///
+	/// Use [`UnixSeqpacketBuilder`] to configure the socket before it is connected.
+	pub async fn connect<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		UnixSeqpacketBuilder::new().connect(address).await
+	}
 
-		let socket = Self::new(socket)?;
-		socket.io.writable().await?.retain_ready();
-		Ok(socket)
+	/// Connect a new seqpacket socket to the given address with a timeout.
+	///
+	/// If the connection is not established before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn connect_timeout<A: ToSocketAddr>(address: A, timeout: Duration) -> std::io::Result<Self> {
+		with_timeout(timeout, Self::connect(address)).await
 	}
 
 	/// Connect a new seqpacket socket to the given address, blocking until the connection is established.
 	///
 	/// Note that the socket has to be registered with the tokio [`Runtime`].
 	/// This means that the function can not be used outside of a tokio runtime, event though it is blocking.
+	/// To use a socket without a tokio runtime, use [`blocking::UnixSeqpacket`](crate::blocking::UnixSeqpacket) instead.
 	///
 	/// [`Runtime`]: https://docs.rs/tokio/1/tokio/runtime/struct.Runtime.html
-	pub fn connect_blocking<P: AsRef<Path>>(address: P) -> std::io::Result<Self> {
-		let mut socket = sys::local_seqpacket_socket_blocking()?;
-		if let Err(e) = sys::connect(&socket, address) {
-			if e.kind() != std::io::ErrorKind::WouldBlock {
-				return Err(e);
-			}
-		}
-
-		// SAFETY: We just created the socket in this scope,
-		// no other thread can soundly be be modifying its flags right now.
-		unsafe {
-			sys::set_non_blocking(&mut socket, true)?;
-		}
-
-		Self::new(socket)
+	pub fn connect_blocking<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		UnixSeqpacketBuilder::new().connect_blocking(address)
 	}
 
 	/// Create a pair of connected seqpacket sockets.
 	pub fn pair() -> std::io::Result<(Self, Self)> {
-		let (a, b) = sys::local_seqpacket_pair()?;
-		Ok((Self::new(a)?, Self::new(b)?))
+		UnixSeqpacketBuilder::new().pair()
 	}
 
 	/// Wrap a raw file descriptor as [`UnixSeqpacket`].
@@ -208,6 +164,24 @@ impl UnixSeqpacket {
 		&self.io
 	}
 
+	/// Get the address of the local half of this connection.
+	///
+	/// Sockets created by [`Self::connect()`] or [`Self::pair()`] are not bound to an address, so their address is unnamed.
+	/// Sockets accepted by a listener report the address of the listener.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_local_address(self.io.get_ref())
+	}
+
+	/// Get the address of the remote half of this connection.
+	///
+	/// For a socket created by [`Self::connect()`], this is the address of the listener it connected to.
+	/// For an accepted socket, this is the address of the client socket, which is unnamed unless the client bound its socket to an address.
+	/// On Linux, a client that has `SO_PASSCRED` enabled is automatically bound to a unique abstract address when it connects.
+	/// Sockets created by [`Self::pair()`] have an unnamed peer address.
+	pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_peer_address(self.io.get_ref())
+	}
+
 	/// Get the effective credentials of the process which called `connect` or `pair`.
 	///
 	/// Note that this is not necessarily the process that currently has the file descriptor of the other side of the connection.
@@ -227,14 +201,7 @@ impl UnixSeqpacket {
 	/// Note that unlike [`Self::send`], only the last task calling this function will be woken up.
 	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
 	pub fn poll_send(&self, cx: &mut Context, buffer: &[u8]) -> Poll<std::io::Result<usize>> {
-		loop {
-			let mut ready_guard = ready!(self.io.poll_write_ready(cx)?);
-
-			match ready_guard.try_io(|inner| sys::send(inner.get_ref(), buffer)) {
-				Ok(result) => return Poll::Ready(result),
-				Err(_would_block) => continue,
-			}
-		}
+		self.io.poll_io(cx, Direction::Write, |fd| sys::send(fd, buffer))
 	}
 
 	/// Try to send data on the socket to the connected peer without blocking.
@@ -274,13 +241,7 @@ impl UnixSeqpacket {
 		buffer: &[IoSlice],
 		ancillary: &mut AncillaryMessageWriter,
 	) -> Poll<std::io::Result<usize>> {
-		loop {
-			let mut ready_guard = ready!(self.io.poll_write_ready(cx)?);
-			match ready_guard.try_io(|inner| sys::send_msg(inner.get_ref(), buffer, ancillary)) {
-				Ok(result) => return Poll::Ready(result),
-				Err(_would_block) => continue,
-			}
-		}
+		readiness::poll_send_msg(&self.io, cx, buffer, ancillary)
 	}
 
 	/// Send data on the socket to the connected peer.
@@ -289,14 +250,7 @@ impl UnixSeqpacket {
 	/// All calling tasks will try to complete the asynchronous action,
 	/// although the order in which they complete is not guaranteed.
 	pub async fn send(&self, buffer: &[u8]) -> std::io::Result<usize> {
-		loop {
-			let mut ready_guard = self.io.writable().await?;
-
-			match ready_guard.try_io(|inner| sys::send(inner.get_ref(), buffer)) {
-				Ok(result) => return result,
-				Err(_would_block) => continue,
-			}
-		}
+		self.io.io(Direction::Write, |fd| sys::send(fd, buffer)).await
 	}
 
 	/// Send data on the socket to the connected peer.
@@ -333,13 +287,71 @@ impl UnixSeqpacket {
 		buffer: &[IoSlice<'_>],
 		ancillary: &mut AncillaryMessageWriter<'_>,
 	) -> std::io::Result<usize> {
-		loop {
-			let mut ready_guard = self.io.writable().await?;
-			match ready_guard.try_io(|inner| sys::send_msg(inner.get_ref(), buffer, ancillary)) {
-				Ok(result) => return result,
-				Err(_would_block) => continue,
-			}
-		}
+		readiness::send_msg(&self.io, buffer, ancillary).await
+	}
+
+	/// Try to send an owned message with its file descriptors on the socket to the connected peer without blocking.
+	///
+	/// If the socket is not ready yet, the current task is scheduled to wake up when the socket becomes writeable.
+	///
+	/// Note that unlike [`Self::send_message`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	pub fn poll_send_message(&self, cx: &mut Context, message: &OutgoingMessage) -> Poll<std::io::Result<usize>> {
+		self.poll_send_with_fds(cx, &message.data, &message.fds)
+	}
+
+	/// Try to send data with file descriptors on the socket to the connected peer without blocking.
+	pub(crate) fn poll_send_with_fds(
+		&self,
+		cx: &mut Context,
+		data: &[u8],
+		fds: &[OwnedFd],
+	) -> Poll<std::io::Result<usize>> {
+		let ancillary_buffer_len = crate::message::fds_ancillary_buffer_len(fds)?;
+		let mut ancillary_buffer = Vec::new();
+		self.io.poll_io(cx, Direction::Write, |fd| {
+			// Only allocate the ancillary buffer once the socket is ready, and only once.
+			ancillary_buffer.resize(ancillary_buffer_len, 0);
+			let mut ancillary = crate::message::fds_ancillary_writer(fds, &mut ancillary_buffer)?;
+			sys::send_msg(fd, &[IoSlice::new(data)], &mut ancillary)
+		})
+	}
+
+	/// Send an owned message with its file descriptors on the socket to the connected peer.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	pub async fn send_message(&self, message: &OutgoingMessage) -> std::io::Result<usize> {
+		let mut ancillary_buffer = vec![0; message.ancillary_buffer_len()?];
+		let mut ancillary = message.ancillary_writer(&mut ancillary_buffer)?;
+		self.send_vectored_with_ancillary(&[IoSlice::new(&message.data)], &mut ancillary)
+			.await
+	}
+
+	/// Send data on the socket to the connected peer with a timeout.
+	///
+	/// If the data can not be sent before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned and no data is sent.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn send_timeout(&self, buffer: &[u8], timeout: Duration) -> std::io::Result<usize> {
+		with_timeout(timeout, self.send(buffer)).await
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer with a timeout.
+	///
+	/// If the data can not be sent before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned and no data is sent.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn send_with_ancillary_timeout(
+		&self,
+		buffer: &[u8],
+		ancillary: &mut AncillaryMessageWriter<'_>,
+		timeout: Duration,
+	) -> std::io::Result<usize> {
+		with_timeout(timeout, self.send_with_ancillary(buffer, ancillary)).await
 	}
 
 	/// Try to receive data on the socket from the connected peer without blocking.
@@ -394,7 +406,7 @@ impl UnixSeqpacket {
 		cx: &mut Context,
 		buffer: &mut [IoSliceMut],
 	) -> Poll<std::io::Result<MessageInfo>> {
-		let (read, _ancillary) = ready!(self.poll_recv_vectored_with_ancillary_internal(cx, buffer, &mut [], true))?;
+		let (read, _ancillary) = ready!(readiness::poll_recv_msg(&self.io, cx, buffer, &mut [], libc::MSG_PEEK))?;
 		Poll::Ready(Ok(read))
 	}
 
@@ -433,7 +445,7 @@ impl UnixSeqpacket {
 		buffer: &mut [IoSliceMut],
 		ancillary_buffer: &'a mut [u8],
 	) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
-		self.poll_recv_vectored_with_ancillary_internal(cx, buffer, ancillary_buffer, false)
+		readiness::poll_recv_msg(&self.io, cx, buffer, ancillary_buffer, 0)
 	}
 
 	/// Try to peek at the next message and its ancillary data on the socket from the connected peer without blocking.
@@ -481,32 +493,7 @@ impl UnixSeqpacket {
 		buffer: &mut [IoSliceMut],
 		ancillary_buffer: &'a mut [u8],
 	) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
-		self.poll_recv_vectored_with_ancillary_internal(cx, buffer, ancillary_buffer, true)
-	}
-
-	/// Shared implementation of `poll_recv_vectored_with_ancillary` and `poll_peek_vectored_with_ancillary`.
-	fn poll_recv_vectored_with_ancillary_internal<'a>(
-		&self,
-		cx: &mut Context,
-		buffer: &mut [IoSliceMut],
-		ancillary_buffer: &'a mut [u8],
-		peek: bool,
-	) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
-		loop {
-			let mut ready_guard = ready!(self.io.poll_read_ready(cx)?);
-
-			let (read, ancillary_reader) =
-				match ready_guard.try_io(|inner| sys::recv_msg(inner.get_ref(), buffer, ancillary_buffer, peek)) {
-					Ok(x) => x?,
-					Err(_would_block) => continue,
-				};
-
-			// SAFETY: We have to work around a borrow checker bug:
-			// It doesn't know that we return in this branch, so the loop terminates.
-			// It thinks we will do another mutable borrow in the next loop iteration.
-			// TODO: Remove this transmute once the borrow checker is smart enough.
-			return Poll::Ready(Ok((read, unsafe { transmute_lifetime(ancillary_reader) })));
-		}
+		readiness::poll_recv_msg(&self.io, cx, buffer, ancillary_buffer, libc::MSG_PEEK)
 	}
 
 	/// Receive data on the socket from the connected peer.
@@ -549,9 +536,7 @@ impl UnixSeqpacket {
 	/// All calling tasks will try to complete the asynchronous action,
 	/// although the order in which they complete is not guaranteed.
 	pub async fn peek_vectored(&self, buffer: &mut [IoSliceMut<'_>]) -> std::io::Result<MessageInfo> {
-		let (read, _ancillary) = self
-			.recv_vectored_with_ancillary_internal(buffer, &mut [], true)
-			.await?;
+		let (read, _ancillary) = readiness::recv_msg(&self.io, buffer, &mut [], libc::MSG_PEEK).await?;
 		Ok(read)
 	}
 
@@ -587,8 +572,7 @@ impl UnixSeqpacket {
 		buffer: &mut [IoSliceMut<'_>],
 		ancillary_buffer: &'a mut [u8],
 	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
-		self.recv_vectored_with_ancillary_internal(buffer, ancillary_buffer, false)
-			.await
+		readiness::recv_msg(&self.io, buffer, ancillary_buffer, 0).await
 	}
 
 	/// Peek at the next message and its ancillary data on the socket from the connected peer.
@@ -633,32 +617,564 @@ impl UnixSeqpacket {
 		buffer: &mut [IoSliceMut<'_>],
 		ancillary_buffer: &'a mut [u8],
 	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
-		self.recv_vectored_with_ancillary_internal(buffer, ancillary_buffer, true)
+		readiness::recv_msg(&self.io, buffer, ancillary_buffer, libc::MSG_PEEK).await
+	}
+
+	/// Try to send multiple messages on the socket to the connected peer with a single system call, without blocking.
+	///
+	/// Returns the number of messages that were sent, which may be less than the number of entries.
+	///
+	/// If the socket is not ready yet, the current task is scheduled to wake up when the socket becomes writeable.
+	///
+	/// Note that unlike [`Self::send_batch`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub fn poll_send_batch(&self, cx: &mut Context, entries: &[crate::SendBatchEntry]) -> Poll<std::io::Result<usize>> {
+		if entries.is_empty() {
+			return Poll::Ready(Ok(0));
+		}
+		self.io.poll_io(cx, Direction::Write, |fd| sys::send_mmsg(fd, entries))
+	}
+
+	/// Send multiple messages on the socket to the connected peer with a single system call.
+	///
+	/// This uses `sendmmsg` to send the messages.
+	/// Returns the number of messages that were sent, which may be less than the number of entries.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub async fn send_batch(&self, entries: &[crate::SendBatchEntry<'_>]) -> std::io::Result<usize> {
+		if entries.is_empty() {
+			return Ok(0);
+		}
+		self.io.io(Direction::Write, |fd| sys::send_mmsg(fd, entries)).await
+	}
+
+	/// Try to receive multiple messages on the socket from the connected peer with a single system call, without blocking.
+	///
+	/// Returns the number of messages that were received.
+	/// The messages are stored in the first entries, in the order they were received.
+	///
+	/// If there is no data ready yet, the current task is scheduled to wake up when the socket becomes readable.
+	///
+	/// Note that unlike [`Self::recv_batch`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub fn poll_recv_batch(
+		&self,
+		cx: &mut Context,
+		entries: &mut [crate::RecvBatchEntry],
+	) -> Poll<std::io::Result<usize>> {
+		if entries.is_empty() {
+			return Poll::Ready(Ok(0));
+		}
+		self.io.poll_io(cx, Direction::Read, |fd| sys::recv_mmsg(fd, entries))
+	}
+
+	/// Receive multiple messages on the socket from the connected peer with a single system call.
+	///
+	/// This uses `recvmmsg` to receive all messages that are available, up to the number of entries.
+	/// Returns the number of messages that were received.
+	/// The messages are stored in the first entries, in the order they were received.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub async fn recv_batch(&self, entries: &mut [crate::RecvBatchEntry<'_>]) -> std::io::Result<usize> {
+		if entries.is_empty() {
+			return Ok(0);
+		}
+		self.io.io(Direction::Read, |fd| sys::recv_mmsg(fd, entries)).await
+	}
+
+	/// Receive a message with all of its data and ancillary data into newly allocated buffers.
+	///
+	/// This function first peeks at the next message with `MSG_PEEK | MSG_TRUNC` to learn the real size of the message.
+	/// While peeking, the ancillary buffer is grown until it can hold all control messages.
+	/// Then the message is received into buffers of the right size,
+	/// and ownership is taken of all received file descriptors.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	///
+	/// If another task receives a message from the same socket in between peeking and receiving,
+	/// the next message may not fit in the allocated buffers.
+	/// In that case, the message is lost and an error of kind [`std::io::ErrorKind::InvalidData`] is returned.
+	#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+	pub async fn recv_message(&self) -> std::io::Result<crate::ReceivedMessage> {
+		let mut ancillary_buffer = vec![0; 64];
+		loop {
+			let (info, ancillary) = readiness::recv_msg(
+				&self.io,
+				&mut [],
+				&mut ancillary_buffer,
+				libc::MSG_PEEK | libc::MSG_TRUNC,
+			)
+			.await?;
+			// Close the duplicated file descriptors from the peeked message.
+			drop(ancillary);
+
+			if info.ancillary_truncated {
+				ancillary_buffer.resize(ancillary_buffer.len() * 2, 0);
+				continue;
+			}
+
+			let mut data = vec![0; info.bytes_read];
+			let (info, ancillary) =
+				readiness::recv_msg(&self.io, &mut [IoSliceMut::new(&mut data)], &mut ancillary_buffer, 0).await?;
+			if info.truncated || info.ancillary_truncated {
+				return Err(std::io::Error::new(
+					std::io::ErrorKind::InvalidData,
+					"message was truncated: it changed between peeking and receiving",
+				));
+			}
+			data.truncate(info.bytes_read);
+			return Ok(crate::ReceivedMessage::from_parts(data, ancillary));
+		}
+	}
+
+	/// Try to get the size of the next message without consuming it and without blocking.
+	///
+	/// If there is no message ready yet, the current task is scheduled to wake up when the socket becomes readable.
+	///
+	/// See [`Self::next_message_len()`] for more information.
+	///
+	/// Note that unlike [`Self::next_message_len`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	pub fn poll_next_message_len(&self, cx: &mut Context) -> Poll<std::io::Result<usize>> {
+		self.io.poll_io(cx, Direction::Read, sys::next_message_len)
+	}
+
+	/// Get the size of the next message without consuming it.
+	///
+	/// The returned size is the full size of the payload of the message, so it can be used to allocate a buffer before receiving it.
+	///
+	/// On Linux and Android, the size is obtained with a single `recvmsg` call with `MSG_PEEK | MSG_TRUNC`.
+	/// The message and its ancillary data are left in the socket, and no file descriptors are received.
+	///
+	/// Other platforms do not report the full size of truncated messages,
+	/// so the message is peeked with a growing buffer until it fits, up to the size of the receive buffer of the socket.
+	/// That requires multiple system calls and a temporary allocation for large messages.
+	/// The message itself is left in the socket, but peeking without an ancillary buffer may discard its ancillary data.
+	/// Any file descriptors attached to the message can be lost on those platforms.
+	///
+	/// Note that an empty message and a connection closed by the peer both report a size of 0.
+	///
+	/// If another task receives a message from the same socket concurrently,
+	/// the reported size may belong to a message that has already been consumed.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	pub async fn next_message_len(&self) -> std::io::Result<usize> {
+		self.io.io(Direction::Read, sys::next_message_len).await
+	}
+
+	/// Receive data on the socket from the connected peer with a timeout.
+	///
+	/// If no message is received before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned and no message is consumed.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn recv_timeout(&self, buffer: &mut [u8], timeout: Duration) -> std::io::Result<MessageInfo> {
+		with_timeout(timeout, self.recv(buffer)).await
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer with a timeout.
+	///
+	/// If no message is received before the timeout expires,
+	/// an error of kind [`std::io::ErrorKind::TimedOut`] is returned and no message is consumed.
+	///
+	/// The timeout uses the tokio timer, so it must be enabled on the runtime.
+	pub async fn recv_with_ancillary_timeout<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+		timeout: Duration,
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		with_timeout(timeout, self.recv_with_ancillary(buffer, ancillary_buffer)).await
+	}
+
+	/// Send the contents of a [`bytes::Buf`] as a single message on the socket to the connected peer.
+	///
+	/// The chunks of the buffer are passed directly to the kernel as an I/O vector, without copying them first.
+	/// If the buffer has more chunks than fit in the I/O vector, or if it does not expose its chunks with [`bytes::Buf::chunks_vectored()`],
+	/// the data is copied into a contiguous buffer first.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "bytes")]
+	pub async fn send_buf(&self, buffer: impl bytes::Buf) -> std::io::Result<usize> {
+		self.send_buf_with_ancillary(buffer, &mut AncillaryMessageWriter::new(&mut []))
 			.await
 	}
 
-	/// Shared implementation of `read_vectored_with_ancillary` and `poll_vectored_with_ancillary`.
-	async fn recv_vectored_with_ancillary_internal<'a>(
+	/// Send the contents of a [`bytes::Buf`] with ancillary data as a single message on the socket to the connected peer.
+	///
+	/// See [`Self::send_buf()`] for more information on how the buffer is sent.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "bytes")]
+	pub async fn send_buf_with_ancillary(
 		&self,
-		buffer: &mut [IoSliceMut<'_>],
+		mut buffer: impl bytes::Buf,
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> std::io::Result<usize> {
+		let mut chunks = [IoSlice::new(&[]); MAX_BUF_CHUNKS];
+		let count = buffer.chunks_vectored(&mut chunks);
+		let chunks = &chunks[..count];
+		if chunks.iter().map(|chunk| chunk.len()).sum::<usize>() == buffer.remaining() {
+			return self.send_vectored_with_ancillary(chunks, ancillary).await;
+		}
+
+		let data = buffer.copy_to_bytes(buffer.remaining());
+		self.send_with_ancillary(&data, ancillary).await
+	}
+
+	/// Receive a message on the socket from the connected peer into the spare capacity of a [`bytes::BufMut`].
+	///
+	/// The message is written directly into the next chunk of uninitialized memory of the buffer, without zero-filling it first.
+	/// If the message does not fit in that chunk, it is truncated.
+	/// For a [`bytes::BytesMut`], make sure to reserve enough capacity for the largest message you expect before calling this function.
+	/// Note that the [`bytes::BufMut::chunk_mut()`] implementation of `BytesMut` reserves only 64 bytes if the buffer has no spare capacity,
+	/// so receiving into a new or full `BytesMut` truncates messages to 64 bytes.
+	///
+	/// The buffer is advanced by the number of bytes received.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "bytes")]
+	pub async fn recv_buf(&self, buffer: &mut impl bytes::BufMut) -> std::io::Result<MessageInfo> {
+		let (info, _ancillary) = self.recv_buf_with_ancillary(buffer, &mut []).await?;
+		Ok(info)
+	}
+
+	/// Receive a message with ancillary data on the socket from the connected peer into the spare capacity of a [`bytes::BufMut`].
+	///
+	/// See [`Self::recv_buf()`] for more information on how the message is written to the buffer.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "bytes")]
+	pub async fn recv_buf_with_ancillary<'a>(
+		&self,
+		buffer: &mut impl bytes::BufMut,
 		ancillary_buffer: &'a mut [u8],
-		peek: bool,
 	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
-		loop {
-			let mut ready_guard = self.io.readable().await?;
-
-			let (read, ancillary_reader) =
-				match ready_guard.try_io(|inner| sys::recv_msg(inner.get_ref(), buffer, ancillary_buffer, peek)) {
-					Ok(x) => x?,
-					Err(_would_block) => continue,
-				};
-
-			// SAFETY: We have to work around a borrow checker bug:
-			// It doesn't know that we return in this branch, so the loop terminates.
-			// It thinks we will do another mutable borrow in the next loop iteration.
-			// TODO: Remove this transmute once the borrow checker is smart enough.
-			return Ok((read, unsafe { transmute_lifetime(ancillary_reader) }));
+		let chunk = buffer.chunk_mut();
+		let (info, ancillary_len) = self
+			.io
+			.io(Direction::Read, |fd| recv_into_chunk(fd, chunk, ancillary_buffer))
+			.await?;
+		// SAFETY: The kernel initialized the first `bytes_read` bytes of the chunk.
+		// Also when the message was truncated, because `bytes_read` never exceeds the size of the chunk.
+		unsafe {
+			buffer.advance_mut(info.bytes_read);
+		}
+		// SAFETY: The buffer contains the ancillary data that we just received.
+		let ancillary =
+			unsafe { sys::ancillary_reader(&mut ancillary_buffer[..ancillary_len], info.ancillary_truncated) };
+		Ok((info, ancillary))
+	}
+
+	/// Try to receive a message with ancillary data into the spare capacity of a [`bytes::BufMut`] without blocking.
+	///
+	/// See [`Self::recv_buf_with_ancillary()`] for more information.
+	#[cfg(feature = "codec")]
+	pub(crate) fn poll_recv_buf_with_ancillary<'a>(
+		&self,
+		cx: &mut Context,
+		buffer: &mut impl bytes::BufMut,
+		ancillary_buffer: &'a mut [u8],
+	) -> Poll<std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>> {
+		let chunk = buffer.chunk_mut();
+		let (info, ancillary_len) = ready!(self.io.poll_io(cx, Direction::Read, |fd| {
+			recv_into_chunk(fd, chunk, ancillary_buffer)
+		}))?;
+		// SAFETY: The kernel initialized the first `bytes_read` bytes of the chunk.
+		// Also when the message was truncated, because `bytes_read` never exceeds the size of the chunk.
+		unsafe {
+			buffer.advance_mut(info.bytes_read);
+		}
+		// SAFETY: The buffer contains the ancillary data that we just received.
+		let ancillary =
+			unsafe { sys::ancillary_reader(&mut ancillary_buffer[..ancillary_len], info.ancillary_truncated) };
+		Poll::Ready(Ok((info, ancillary)))
+	}
+
+	/// Encode a value with the given format and send it as a single message on the socket to the connected peer.
+	///
+	/// The value is encoded directly into the buffer that is passed to the kernel.
+	/// File descriptors in the value are sent as ancillary data, see the [`format::fd`][crate::format::fd] module.
+	///
+	/// This function allocates a new buffer for every message.
+	/// Use [`Self::send_value_with_buffer()`] to reuse the same buffer for multiple messages.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "serde")]
+	pub async fn send_value<F, T>(&self, format: F, value: &T) -> Result<usize, crate::ValueError>
+	where
+		F: crate::format::Format,
+		T: serde::Serialize + ?Sized,
+	{
+		self.send_value_with_buffer(format, value, &mut Vec::new()).await
+	}
+
+	/// Encode a value with the given format into the given buffer and send it as a single message on the socket to the connected peer.
+	///
+	/// The buffer is cleared before the value is encoded into it, but it keeps its capacity.
+	/// Reusing the same buffer for multiple messages avoids allocating a new buffer for every message.
+	///
+	/// See [`Self::send_value()`] for more information.
+	#[cfg(feature = "serde")]
+	pub async fn send_value_with_buffer<F, T>(
+		&self,
+		format: F,
+		value: &T,
+		buffer: &mut Vec<u8>,
+	) -> Result<usize, crate::ValueError>
+	where
+		F: crate::format::Format,
+		T: serde::Serialize + ?Sized,
+	{
+		buffer.clear();
+		let fds = crate::format::fd::encode_with_fds(&format, value, buffer).map_err(crate::ValueError::Encode)?;
+		if fds.is_empty() {
+			return Ok(self.send(buffer).await?);
+		}
+		let mut ancillary_buffer = vec![0; crate::message::fds_ancillary_buffer_len(&fds)?];
+		let mut ancillary = crate::message::fds_ancillary_writer(&fds, &mut ancillary_buffer)?;
+		Ok(self.send_with_ancillary(buffer, &mut ancillary).await?)
+	}
+
+	/// Receive a single message on the socket from the connected peer and decode it as a value with the given format.
+	///
+	/// The message is received in the given buffer.
+	/// If the message does not fit in the buffer, it is consumed anyway and [`ValueError::Truncated`][crate::ValueError::Truncated] is returned.
+	/// Use [`Self::next_message_len()`] if you need to know the size of the message in advance.
+	///
+	/// Any ancillary data of the message is discarded.
+	/// Use [`Self::recv_value_with_fds()`] to receive values that contain file descriptors.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "serde")]
+	pub async fn recv_value<F, T>(&self, format: F, buffer: &mut [u8]) -> Result<T, crate::ValueError>
+	where
+		F: crate::format::Format,
+		T: serde::de::DeserializeOwned,
+	{
+		let info = self.recv(buffer).await?;
+		if info.truncated {
+			return Err(crate::ValueError::Truncated {
+				buffer_len: buffer.len(),
+			});
 		}
+		crate::format::fd::decode_with_fds(&format, &buffer[..info.bytes_read], Vec::new())
+			.map_err(crate::ValueError::Decode)
+	}
+
+	/// Receive a single message with file descriptors on the socket from the connected peer and decode it as a value with the given format.
+	///
+	/// The message is received in the given buffer, and the ancillary data in the given ancillary buffer.
+	/// The received file descriptors are available to fields of the value, see the [`format::fd`][crate::format::fd] module.
+	/// File descriptors that are not claimed by the value are closed.
+	///
+	/// If the message does not fit in the buffer, it is consumed anyway and [`ValueError::Truncated`][crate::ValueError::Truncated] is returned.
+	/// If the ancillary data does not fit in the ancillary buffer, [`ValueError::AncillaryTruncated`][crate::ValueError::AncillaryTruncated] is returned.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	/// All calling tasks will try to complete the asynchronous action,
+	/// although the order in which they complete is not guaranteed.
+	#[cfg(feature = "serde")]
+	pub async fn recv_value_with_fds<F, T>(
+		&self,
+		format: F,
+		buffer: &mut [u8],
+		ancillary_buffer: &mut [u8],
+	) -> Result<T, crate::ValueError>
+	where
+		F: crate::format::Format,
+		T: serde::de::DeserializeOwned,
+	{
+		let ancillary_buffer_len = ancillary_buffer.len();
+		let (info, ancillary) = self.recv_with_ancillary(buffer, ancillary_buffer).await?;
+		let mut fds = Vec::new();
+		for message in ancillary.into_messages() {
+			if let crate::ancillary::OwnedAncillaryMessage::FileDescriptors(received) = message {
+				fds.extend(received);
+			}
+		}
+		if info.truncated {
+			return Err(crate::ValueError::Truncated {
+				buffer_len: buffer.len(),
+			});
+		}
+		if info.ancillary_truncated {
+			return Err(crate::ValueError::AncillaryTruncated {
+				buffer_len: ancillary_buffer_len,
+			});
+		}
+		crate::format::fd::decode_with_fds(&format, &buffer[..info.bytes_read], fds).map_err(crate::ValueError::Decode)
+	}
+
+	/// Wait for any of the requested ready states.
+	///
+	/// This function is usually paired with the `try_` functions, such as [`Self::try_recv`] and [`Self::try_send`].
+	/// The returned [`Ready`] value can also be used to detect that the peer hung up, without reading from the socket:
+	/// [`Ready::is_read_closed()`] is set when the peer will not send any more messages,
+	/// and [`Ready::is_write_closed()`] is set when the peer will not receive any more messages.
+	///
+	/// The readiness may be a false positive.
+	/// If a `try_` function subsequently fails with [`std::io::ErrorKind::WouldBlock`], the readiness is cleared again.
+	///
+	/// This function is safe to call concurrently from different tasks.
+	pub async fn ready(&self, interest: Interest) -> std::io::Result<Ready> {
+		let mut guard = self.io.ready(interest).await?;
+		let ready = guard.ready();
+		guard.retain_ready();
+		Ok(ready)
+	}
+
+	/// Wait for the socket to become readable.
+	///
+	/// This is equivalent to `self.ready(Interest::READABLE)`, but it does not return the ready state.
+	/// A socket also becomes readable when the peer closes the connection.
+	/// See [`Self::ready()`] for more information.
+	pub async fn readable(&self) -> std::io::Result<()> {
+		self.ready(Interest::READABLE).await?;
+		Ok(())
+	}
+
+	/// Wait for the socket to become writable.
+	///
+	/// This is equivalent to `self.ready(Interest::WRITABLE)`, but it does not return the ready state.
+	/// See [`Self::ready()`] for more information.
+	pub async fn writable(&self) -> std::io::Result<()> {
+		self.ready(Interest::WRITABLE).await?;
+		Ok(())
+	}
+
+	/// Poll the socket for read readiness.
+	///
+	/// If the socket is not readable yet, the current task is scheduled to wake up when it becomes readable.
+	///
+	/// Note that unlike [`Self::readable`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	pub fn poll_recv_ready(&self, cx: &mut Context) -> Poll<std::io::Result<()>> {
+		let mut guard = ready!(self.io.poll_read_ready(cx)?);
+		guard.retain_ready();
+		Poll::Ready(Ok(()))
+	}
+
+	/// Poll the socket for write readiness.
+	///
+	/// If the socket is not writable yet, the current task is scheduled to wake up when it becomes writable.
+	///
+	/// Note that unlike [`Self::writable`], only the last task calling this function will be woken up.
+	/// For that reason, it is preferable to use the async functions rather than polling functions when possible.
+	pub fn poll_send_ready(&self, cx: &mut Context) -> Poll<std::io::Result<()>> {
+		let mut guard = ready!(self.io.poll_write_ready(cx)?);
+		guard.retain_ready();
+		Poll::Ready(Ok(()))
+	}
+
+	/// Try to send data on the socket to the connected peer, without waiting for the socket to become writable.
+	///
+	/// If the socket is not known to be writable, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// In that case, the readiness of the socket is cleared and the message is not sent.
+	///
+	/// Unlike [`Self::poll_send`], this does not register the current task for wake-up.
+	/// It can be used from synchronous code, such as [`Drop`] implementations.
+	pub fn try_send(&self, buffer: &[u8]) -> std::io::Result<usize> {
+		self.io.try_io(Interest::WRITABLE, |inner| sys::send(inner, buffer))
+	}
+
+	/// Try to send data with ancillary data on the socket to the connected peer, without waiting for the socket to become writable.
+	///
+	/// If the socket is not known to be writable, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// In that case, the readiness of the socket is cleared and the message is not sent.
+	///
+	/// See [`Self::try_send`] for more information.
+	pub fn try_send_with_ancillary(
+		&self,
+		buffer: &[u8],
+		ancillary: &mut AncillaryMessageWriter,
+	) -> std::io::Result<usize> {
+		self.io.try_io(Interest::WRITABLE, |inner| {
+			sys::send_msg(inner, &[IoSlice::new(buffer)], ancillary)
+		})
+	}
+
+	/// Try to receive data on the socket from the connected peer, without waiting for the socket to become readable.
+	///
+	/// If no message is known to be available, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// In that case, the readiness of the socket is cleared.
+	///
+	/// Unlike [`Self::poll_recv`], this does not register the current task for wake-up.
+	/// Note that the socket is only known to be readable after the tokio runtime has been notified of it.
+	/// Use [`Self::readable`] first if you need to be sure to receive a message that was just sent.
+	pub fn try_recv(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) = self.try_recv_internal(buffer, &mut [], 0)?;
+		Ok(read)
+	}
+
+	/// Try to peek at the next message on the socket from the connected peer, without waiting for the socket to become readable.
+	///
+	/// Peeking a message with ancillary data may consume or omit it on some platforms.
+	/// See the [`UnixSeqpacket`] documentation for more information.
+	///
+	/// If no message is known to be available, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// See [`Self::try_recv`] for more information.
+	pub fn try_peek(&self, buffer: &mut [u8]) -> std::io::Result<MessageInfo> {
+		let (read, _ancillary) = self.try_recv_internal(buffer, &mut [], libc::MSG_PEEK)?;
+		Ok(read)
+	}
+
+	/// Try to receive data with ancillary data on the socket from the connected peer, without waiting for the socket to become readable.
+	///
+	/// Any file descriptors received in the anicallary data will have the `close-on-exec` flag set.
+	/// If the OS supports it, this is done atomically with the reception of the message.
+	/// However, on Illumos and Solaris, the `close-on-exec` flag is set in a separate step after receiving the message.
+	///
+	/// If no message is known to be available, an error of kind [`std::io::ErrorKind::WouldBlock`] is returned.
+	/// See [`Self::try_recv`] for more information.
+	pub fn try_recv_with_ancillary<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		self.try_recv_internal(buffer, ancillary_buffer, 0)
+	}
+
+	/// Shared implementation of the `try_recv` and `try_peek` functions.
+	fn try_recv_internal<'a>(
+		&self,
+		buffer: &mut [u8],
+		ancillary_buffer: &'a mut [u8],
+		flags: c_int,
+	) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+		self.io.try_io(Interest::READABLE, |inner| {
+			sys::recv_msg(inner, &mut [IoSliceMut::new(buffer)], ancillary_buffer, flags)
+		})
 	}
 
 	/// Shuts down the read, write, or both halves of this connection.
@@ -671,6 +1187,8 @@ impl UnixSeqpacket {
 	}
 }
 
+impl_socket_options!(UnixSeqpacket);
+
 impl AsRawFd for UnixSeqpacket {
 	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
 		self.as_raw_fd()
@@ -683,13 +1201,37 @@ impl IntoRawFd for UnixSeqpacket {
 	}
 }
 
-/// Transmute the lifetime of a `AncillaryMessageReader`.
+/// Run an I/O future with a timeout.
+///
+/// If the timeout expires, the future is dropped and an error of kind [`std::io::ErrorKind::TimedOut`] is returned.
 ///
-/// Exists to ensure we do not accidentally transmute more than we intend to.
+/// This is only cancel-safe if the future is.
+/// The I/O functions in this crate only perform a system call once the socket is ready,
+/// and the system call itself is never interrupted by the timeout.
+pub(crate) async fn with_timeout<T>(
+	timeout: Duration,
+	future: impl std::future::Future<Output = std::io::Result<T>>,
+) -> std::io::Result<T> {
+	match tokio::time::timeout(timeout, future).await {
+		Ok(result) => result,
+		Err(_elapsed) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "operation timed out")),
+	}
+}
+
+/// Receive a message into a chunk of possibly uninitialized memory.
 ///
-/// # Safety
-/// All the safety requirements of [`std::mem::transmute`] should be uphold.
-#[allow(clippy::needless_lifetimes)]
-unsafe fn transmute_lifetime<'a, 'b>(input: AncillaryMessageReader<'a>) -> AncillaryMessageReader<'b> {
-	std::mem::transmute(input)
+/// Returns the message info and the length of the received ancillary data.
+#[cfg(feature = "bytes")]
+fn recv_into_chunk(
+	socket: &FileDesc,
+	chunk: &mut bytes::buf::UninitSlice,
+	ancillary_buffer: &mut [u8],
+) -> std::io::Result<(MessageInfo, usize)> {
+	// The chunk may be uninitialized, so we pass it to the kernel as a raw `iovec` instead of a slice.
+	let iov = [libc::iovec {
+		iov_base: chunk.as_mut_ptr().cast(),
+		iov_len: chunk.len(),
+	}];
+	// SAFETY: The chunk is valid for writes of `chunk.len()` bytes, and the kernel only writes initialized bytes.
+	unsafe { sys::recv_msg_iovec(socket, &iov, ancillary_buffer, 0) }
 }
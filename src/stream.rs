@@ -0,0 +1,147 @@
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::io::IoSliceMut;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{OutgoingMessage, ReceivedMessage, UnixSeqpacket};
+
+/// Default capacity of the receive buffer of a [`SeqpacketStream`].
+pub(crate) const DEFAULT_MESSAGE_CAPACITY: usize = 64 * 1024;
+
+/// Default capacity of the ancillary receive buffer of a [`SeqpacketStream`].
+pub(crate) const DEFAULT_ANCILLARY_CAPACITY: usize = 1024;
+
+/// Adapter implementing [`Stream`] and [`Sink`] for a [`UnixSeqpacket`].
+///
+/// Each item yielded by the stream is exactly one received message,
+/// and each item sent into the sink is sent as exactly one message.
+///
+/// Messages are received in internal buffers with a fixed capacity,
+/// and then copied into a [`ReceivedMessage`].
+/// If a message or its ancillary data does not fit in the internal buffers,
+/// the stream yields an error of kind [`std::io::ErrorKind::InvalidData`] and the message is lost.
+///
+/// The stream ends when an empty message without ancillary data is received.
+/// This happens when the peer closed their half of the connection.
+///
+/// The socket can not distinguish the end of the connection from an empty message that was sent by the peer.
+/// As a result, an empty message without ancillary data also ends the stream, even if the connection is still open.
+/// If your protocol uses empty messages, use the socket directly and handle them yourself.
+///
+/// Closing the sink shuts down the write half of the socket.
+///
+/// Unlike the async functions of [`UnixSeqpacket`], only the last task polling the stream or sink will be woken up.
+/// Use the socket directly if multiple tasks need to send or receive at the same time.
+#[derive(Debug)]
+pub struct SeqpacketStream {
+	socket: UnixSeqpacket,
+	recv_buffer: Vec<u8>,
+	ancillary_buffer: Vec<u8>,
+	pending: Option<OutgoingMessage>,
+	eof: bool,
+}
+
+impl SeqpacketStream {
+	/// Create a new stream for a socket with the default receive buffer capacities.
+	///
+	/// The default capacities are 64 KiB for the message data, and 1 KiB for the ancillary data.
+	pub fn new(socket: UnixSeqpacket) -> Self {
+		Self::with_capacity(socket, DEFAULT_MESSAGE_CAPACITY, DEFAULT_ANCILLARY_CAPACITY)
+	}
+
+	/// Create a new stream for a socket with the given receive buffer capacities.
+	///
+	/// Received messages with more than `message_capacity` bytes of data,
+	/// or with more than `ancillary_capacity` bytes of ancillary data are reported as errors.
+	pub fn with_capacity(socket: UnixSeqpacket, message_capacity: usize, ancillary_capacity: usize) -> Self {
+		Self {
+			socket,
+			recv_buffer: vec![0; message_capacity],
+			ancillary_buffer: vec![0; ancillary_capacity],
+			pending: None,
+			eof: false,
+		}
+	}
+
+	/// Get a reference to the wrapped socket.
+	pub fn get_ref(&self) -> &UnixSeqpacket {
+		&self.socket
+	}
+
+	/// Consume the stream and return the wrapped socket.
+	///
+	/// A message that was accepted by the sink but not yet flushed is dropped without being sent.
+	pub fn into_inner(self) -> UnixSeqpacket {
+		self.socket
+	}
+}
+
+impl From<UnixSeqpacket> for SeqpacketStream {
+	fn from(socket: UnixSeqpacket) -> Self {
+		Self::new(socket)
+	}
+}
+
+impl Stream for SeqpacketStream {
+	type Item = std::io::Result<ReceivedMessage>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		if this.eof {
+			return Poll::Ready(None);
+		}
+
+		let (info, ancillary) = ready!(this.socket.poll_recv_vectored_with_ancillary(
+			cx,
+			&mut [IoSliceMut::new(&mut this.recv_buffer)],
+			&mut this.ancillary_buffer
+		))?;
+
+		if info.truncated() || info.ancillary_truncated() {
+			return Poll::Ready(Some(Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				"received message does not fit in the receive buffer",
+			))));
+		}
+
+		if info.bytes_read() == 0 && ancillary.is_empty() {
+			this.eof = true;
+			return Poll::Ready(None);
+		}
+
+		let data = this.recv_buffer[..info.bytes_read()].to_vec();
+		Poll::Ready(Some(Ok(ReceivedMessage::from_parts(data, ancillary))))
+	}
+}
+
+impl Sink<OutgoingMessage> for SeqpacketStream {
+	type Error = std::io::Error;
+
+	fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.poll_flush(cx)
+	}
+
+	fn start_send(self: Pin<&mut Self>, item: OutgoingMessage) -> Result<(), Self::Error> {
+		let this = self.get_mut();
+		debug_assert!(this.pending.is_none(), "start_send called without poll_ready");
+		this.pending = Some(item);
+		Ok(())
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		let this = self.get_mut();
+		if let Some(message) = &this.pending {
+			let result = ready!(this.socket.poll_send_message(cx, message));
+			// Drop the message even if sending failed, so we don't keep trying to send it.
+			this.pending = None;
+			result?;
+		}
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		ready!(self.as_mut().poll_flush(cx))?;
+		Poll::Ready(self.socket.shutdown(std::net::Shutdown::Write))
+	}
+}
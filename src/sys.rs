@@ -2,20 +2,20 @@ use filedesc::FileDesc;
 use std::convert::TryInto;
 use std::io::{IoSlice, IoSliceMut};
 use std::os::raw::{c_int, c_void};
-use std::path::{Path, PathBuf};
 
 use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
-use crate::MessageInfo;
+use crate::{MessageInfo, SocketAddr};
 
 const SOCKET_FLAGS: c_int = libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK;
 const SOCKET_TYPE: c_int = libc::SOCK_SEQPACKET | SOCKET_FLAGS;
-const SEND_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL | libc::MSG_EOR;
+pub const SEND_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL | libc::MSG_EOR;
 
 #[cfg(any(target_os = "illumos", target_os = "solaris"))]
-const RECV_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL;
+pub const RECV_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL;
 #[cfg(not(any(target_os = "illumos", target_os = "solaris")))]
-const RECV_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL | libc::MSG_CMSG_CLOEXEC;
+pub const RECV_MSG_DEFAULT_FLAGS: c_int = libc::MSG_NOSIGNAL | libc::MSG_CMSG_CLOEXEC;
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub fn local_seqpacket_socket_non_blocking() -> std::io::Result<FileDesc> {
 	unsafe {
 		let fd = check(libc::socket(libc::AF_UNIX, SOCKET_TYPE, 0))?;
@@ -30,6 +30,7 @@ pub fn local_seqpacket_socket_blocking() -> std::io::Result<FileDesc> {
 	}
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub fn local_seqpacket_pair() -> std::io::Result<(FileDesc, FileDesc)> {
 	unsafe {
 		let mut fds: [c_int; 2] = [0, 0];
@@ -38,6 +39,20 @@ pub fn local_seqpacket_pair() -> std::io::Result<(FileDesc, FileDesc)> {
 	}
 }
 
+pub fn local_seqpacket_pair_blocking() -> std::io::Result<(FileDesc, FileDesc)> {
+	unsafe {
+		let mut fds: [c_int; 2] = [0, 0];
+		check(libc::socketpair(
+			libc::AF_UNIX,
+			SOCKET_TYPE & !libc::SOCK_NONBLOCK,
+			0,
+			fds.as_mut_ptr(),
+		))?;
+		Ok((FileDesc::from_raw_fd(fds[0]), FileDesc::from_raw_fd(fds[1])))
+	}
+}
+
+#[cfg(feature = "tokio")]
 /// Set the underlying object of a file descriptor in blocking or non-blocking mode.
 ///
 /// SAFETY: This is not an atomic operation.
@@ -55,8 +70,8 @@ pub unsafe fn set_non_blocking(fd: &mut FileDesc, non_blocking: bool) -> std::io
 	Ok(())
 }
 
-pub fn connect<P: AsRef<Path>>(socket: &FileDesc, address: P) -> std::io::Result<()> {
-	let (address, addr_len) = path_to_sockaddr(address.as_ref())?;
+pub fn connect(socket: &FileDesc, address: &SocketAddr) -> std::io::Result<()> {
+	let (address, addr_len) = socket_addr_to_sockaddr(address)?;
 	unsafe {
 		check(libc::connect(
 			socket.as_raw_fd(),
@@ -67,8 +82,8 @@ pub fn connect<P: AsRef<Path>>(socket: &FileDesc, address: P) -> std::io::Result
 	}
 }
 
-pub fn bind<P: AsRef<Path>>(socket: &FileDesc, address: P) -> std::io::Result<()> {
-	let (address, addr_len) = path_to_sockaddr(address.as_ref())?;
+pub fn bind(socket: &FileDesc, address: &SocketAddr) -> std::io::Result<()> {
+	let (address, addr_len) = socket_addr_to_sockaddr(address)?;
 	unsafe {
 		check(libc::bind(
 			socket.as_raw_fd(),
@@ -79,6 +94,23 @@ pub fn bind<P: AsRef<Path>>(socket: &FileDesc, address: P) -> std::io::Result<()
 	}
 }
 
+#[cfg(feature = "tokio")]
+/// Create a new directory with mode 0700 and a unique name.
+///
+/// The last six characters of the template must be `XXXXXX`, they are replaced to make the name unique.
+pub fn make_temp_dir(template: &std::path::Path) -> std::io::Result<std::path::PathBuf> {
+	use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+	let mut template = std::ffi::CString::new(template.as_os_str().as_bytes())?.into_bytes_with_nul();
+	unsafe {
+		if libc::mkdtemp(template.as_mut_ptr().cast()).is_null() {
+			return Err(std::io::Error::last_os_error());
+		}
+	}
+	template.pop();
+	Ok(std::ffi::OsString::from_vec(template).into())
+}
+
 pub fn listen(socket: &FileDesc, backlog: c_int) -> std::io::Result<()> {
 	unsafe {
 		check(libc::listen(socket.as_raw_fd(), backlog))?;
@@ -86,7 +118,16 @@ pub fn listen(socket: &FileDesc, backlog: c_int) -> std::io::Result<()> {
 	}
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub fn accept(socket: &FileDesc) -> std::io::Result<FileDesc> {
+	accept_with_flags(socket, SOCKET_FLAGS)
+}
+
+pub fn accept_blocking(socket: &FileDesc) -> std::io::Result<FileDesc> {
+	accept_with_flags(socket, SOCKET_FLAGS & !libc::SOCK_NONBLOCK)
+}
+
+fn accept_with_flags(socket: &FileDesc, flags: c_int) -> std::io::Result<FileDesc> {
 	unsafe {
 		let mut addr: libc::sockaddr_un = core::mem::zeroed();
 		let mut addr_len: libc::socklen_t = 0;
@@ -94,7 +135,7 @@ pub fn accept(socket: &FileDesc) -> std::io::Result<FileDesc> {
 			socket.as_raw_fd(),
 			&mut addr as *mut _ as *mut _,
 			&mut addr_len,
-			SOCKET_FLAGS,
+			flags,
 		))?;
 		Ok(FileDesc::from_raw_fd(fd))
 	}
@@ -113,34 +154,177 @@ pub fn shutdown(socket: &FileDesc, how: std::net::Shutdown) -> std::io::Result<(
 }
 
 pub fn take_socket_error(socket: &FileDesc) -> std::io::Result<Option<std::io::Error>> {
+	let error = get_int_option(socket, libc::SOL_SOCKET, libc::SO_ERROR)?;
+	if error == 0 {
+		Ok(None)
+	} else {
+		Ok(Some(std::io::Error::from_raw_os_error(error)))
+	}
+}
+
+/// Get the value of a socket option of type `c_int`.
+pub fn get_int_option(socket: &FileDesc, level: c_int, name: c_int) -> std::io::Result<c_int> {
 	unsafe {
-		let mut error: c_int = 0;
+		let mut value: c_int = 0;
 		let mut len = core::mem::size_of::<c_int>() as libc::socklen_t;
+		check(libc::getsockopt(
+			socket.as_raw_fd(),
+			level,
+			name,
+			&mut value as *mut c_int as *mut c_void,
+			&mut len,
+		))?;
+		Ok(value)
+	}
+}
+
+#[cfg(feature = "tokio")]
+/// Set the value of a socket option of type `c_int`.
+pub fn set_int_option(socket: &FileDesc, level: c_int, name: c_int, value: c_int) -> std::io::Result<()> {
+	unsafe {
+		check(libc::setsockopt(
+			socket.as_raw_fd(),
+			level,
+			name,
+			&value as *const c_int as *const c_void,
+			core::mem::size_of::<c_int>() as libc::socklen_t,
+		))?;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "tokio")]
+/// Get the size of a socket buffer from a socket option.
+pub fn get_buffer_size(socket: &FileDesc, name: c_int) -> std::io::Result<usize> {
+	let size = get_int_option(socket, libc::SOL_SOCKET, name)?;
+	size.try_into()
+		.map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "socket buffer size is negative"))
+}
+
+#[cfg(feature = "tokio")]
+/// Set the size of a socket buffer with a socket option.
+pub fn set_buffer_size(socket: &FileDesc, name: c_int, size: usize) -> std::io::Result<()> {
+	let size = size.try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+	set_int_option(socket, libc::SOL_SOCKET, name, size)
+}
+
+#[cfg(feature = "tokio")]
+/// Get the value of a boolean socket option.
+pub fn get_bool_option(socket: &FileDesc, name: c_int) -> std::io::Result<bool> {
+	Ok(get_int_option(socket, libc::SOL_SOCKET, name)? != 0)
+}
+
+/// Set the value of a boolean socket option.
+#[cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+pub fn set_bool_option(socket: &FileDesc, name: c_int, value: bool) -> std::io::Result<()> {
+	set_int_option(socket, libc::SOL_SOCKET, name, value.into())
+}
+
+pub fn get_local_address(socket: &FileDesc) -> std::io::Result<SocketAddr> {
+	unsafe {
+		let mut addr: libc::sockaddr_un = core::mem::zeroed();
+		let mut len = core::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+		check(libc::getsockname(
+			socket.as_raw_fd(),
+			&mut addr as *mut _ as *mut _,
+			&mut len,
+		))?;
+		sockaddr_to_socket_addr(&addr, len)
+	}
+}
+
+/// Get the value of a timeout socket option.
+///
+/// A timeout of zero means the operation never times out, which is reported as `None`.
+pub fn get_timeout(socket: &FileDesc, name: c_int) -> std::io::Result<Option<std::time::Duration>> {
+	unsafe {
+		let mut value: libc::timeval = core::mem::zeroed();
+		let mut len = core::mem::size_of::<libc::timeval>() as libc::socklen_t;
 		check(libc::getsockopt(
 			socket.as_raw_fd(),
 			libc::SOL_SOCKET,
-			libc::SO_ERROR,
-			&mut error as *mut c_int as *mut c_void,
+			name,
+			&mut value as *mut libc::timeval as *mut c_void,
 			&mut len,
 		))?;
-		if error == 0 {
+		if value.tv_sec == 0 && value.tv_usec == 0 {
 			Ok(None)
 		} else {
-			Ok(Some(std::io::Error::from_raw_os_error(error)))
+			// This is not a no-op on all platforms.
+			#[allow(clippy::unnecessary_cast)]
+			let duration = std::time::Duration::new(value.tv_sec as u64, value.tv_usec as u32 * 1000);
+			Ok(Some(duration))
 		}
 	}
 }
 
-pub fn get_local_address(socket: &FileDesc) -> std::io::Result<PathBuf> {
+/// Set the value of a timeout socket option.
+///
+/// A timeout of `None` means the operation never times out.
+/// A zero duration is rejected, since the OS would interpret it as no timeout.
+pub fn set_timeout(socket: &FileDesc, name: c_int, timeout: Option<std::time::Duration>) -> std::io::Result<()> {
+	let value = match timeout {
+		None => libc::timeval { tv_sec: 0, tv_usec: 0 },
+		Some(timeout) if timeout.is_zero() => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"cannot set a zero duration timeout",
+			));
+		},
+		Some(timeout) => {
+			let mut value = libc::timeval {
+				// Clamp to a value that fits in `time_t` on every platform, which is still more than 68 years.
+				tv_sec: timeout.as_secs().min(i32::MAX as u64) as _,
+				tv_usec: timeout.subsec_micros() as _,
+			};
+			// Round up sub-microsecond timeouts, so they don't become infinite.
+			if value.tv_sec == 0 && value.tv_usec == 0 {
+				value.tv_usec = 1;
+			}
+			value
+		},
+	};
+	unsafe {
+		check(libc::setsockopt(
+			socket.as_raw_fd(),
+			libc::SOL_SOCKET,
+			name,
+			&value as *const libc::timeval as *const c_void,
+			core::mem::size_of::<libc::timeval>() as libc::socklen_t,
+		))?;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "tokio")]
+/// Get the address family of a socket.
+pub fn get_socket_family(socket: &FileDesc) -> std::io::Result<c_int> {
+	unsafe {
+		let mut addr: libc::sockaddr_storage = core::mem::zeroed();
+		let mut len = core::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+		check(libc::getsockname(
+			socket.as_raw_fd(),
+			&mut addr as *mut _ as *mut _,
+			&mut len,
+		))?;
+		Ok(addr.ss_family.into())
+	}
+}
+
+pub fn get_peer_address(socket: &FileDesc) -> std::io::Result<SocketAddr> {
 	unsafe {
 		let mut addr: libc::sockaddr_un = core::mem::zeroed();
 		let mut len = core::mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
-		check(libc::getsockname(
+		check(libc::getpeername(
 			socket.as_raw_fd(),
 			&mut addr as *mut _ as *mut _,
 			&mut len,
 		))?;
-		Ok(sockaddr_to_path(&addr, len)?.to_path_buf())
+		sockaddr_to_socket_addr(&addr, len)
 	}
 }
 
@@ -198,8 +382,46 @@ pub fn recv_msg<'a>(
 	socket: &FileDesc,
 	buffer: &mut [IoSliceMut],
 	ancillary_buffer: &'a mut [u8],
-	peek: bool,
+	flags: c_int,
 ) -> std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)> {
+	let (msg_info, ancillary_len) = recv_msg_raw(socket, buffer, ancillary_buffer, flags)?;
+	// SAFETY: The buffer contains the ancillary data that we just received.
+	let ancillary_reader =
+		unsafe { ancillary_reader(&mut ancillary_buffer[..ancillary_len], msg_info.ancillary_truncated) };
+	Ok((msg_info, ancillary_reader))
+}
+
+/// Receive a message without parsing the ancillary data.
+///
+/// Returns the message info and the length of the ancillary data that was written to the start of `ancillary_buffer`.
+/// Use [`ancillary_reader()`] to parse the received ancillary data.
+///
+/// This allows the caller to retry the operation in a loop without keeping a borrow of the ancillary buffer alive.
+pub fn recv_msg_raw(
+	socket: &FileDesc,
+	buffer: &mut [IoSliceMut],
+	ancillary_buffer: &mut [u8],
+	flags: c_int,
+) -> std::io::Result<(MessageInfo, usize)> {
+	// SAFETY: `IoSliceMut` is ABI compatible with `iovec`, and the slices are valid for writes.
+	unsafe {
+		let iov = std::slice::from_raw_parts(buffer.as_ptr().cast::<libc::iovec>(), buffer.len());
+		recv_msg_iovec(socket, iov, ancillary_buffer, flags)
+	}
+}
+
+/// Receive a message into the memory described by raw `iovec`s.
+///
+/// Unlike [`IoSliceMut`], an `iovec` can describe uninitialized memory.
+///
+/// # Safety
+/// Each `iovec` must describe memory that is valid for writes of `iov_len` bytes.
+pub unsafe fn recv_msg_iovec(
+	socket: &FileDesc,
+	iov: &[libc::iovec],
+	ancillary_buffer: &mut [u8],
+	flags: c_int,
+) -> std::io::Result<(MessageInfo, usize)> {
 	let control_data = match ancillary_buffer.len() {
 		0 => std::ptr::null_mut(),
 		_ => ancillary_buffer.as_mut_ptr() as *mut std::os::raw::c_void,
@@ -208,11 +430,11 @@ pub fn recv_msg<'a>(
 	let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
 	header.msg_name = std::ptr::null_mut();
 	header.msg_namelen = 0;
-	header.msg_iov = buffer.as_ptr() as *mut libc::iovec;
+	header.msg_iov = iov.as_ptr() as *mut libc::iovec;
 	// This is not a no-op on all platforms.
 	#[allow(clippy::useless_conversion)]
 	{
-		header.msg_iovlen = buffer.len().try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+		header.msg_iovlen = iov.len().try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
 	}
 	header.msg_flags = 0;
 	header.msg_control = control_data;
@@ -225,12 +447,11 @@ pub fn recv_msg<'a>(
 			.map_err(|_| std::io::ErrorKind::InvalidInput)?;
 	}
 
-	let peek_flag = if peek { libc::MSG_PEEK } else { 0 };
 	let bytes_read = unsafe {
 		check_size(libc::recvmsg(
 			socket.as_raw_fd(),
 			&mut header as *mut _,
-			RECV_MSG_DEFAULT_FLAGS | peek_flag,
+			RECV_MSG_DEFAULT_FLAGS | flags,
 		))?
 	};
 
@@ -243,12 +464,146 @@ pub fn recv_msg<'a>(
 	// This is not a no-op on all platforms.
 	#[allow(clippy::unnecessary_cast)]
 	let length = header.msg_controllen as usize;
-	let ancillary_reader =
-		unsafe { AncillaryMessageReader::new(&mut ancillary_buffer[..length], msg_info.ancillary_truncated) };
+	Ok((msg_info, length))
+}
 
+/// Wrap the ancillary data received by [`recv_msg_raw()`] in a reader.
+///
+/// # Safety
+/// The buffer must contain exactly the ancillary data received by [`recv_msg_raw()`],
+/// and the reader must be created only once for the received data.
+pub unsafe fn ancillary_reader(ancillary: &mut [u8], truncated: bool) -> AncillaryMessageReader<'_> {
+	let ancillary_reader = AncillaryMessageReader::new(ancillary, truncated);
 	#[cfg(any(target_os = "illumos", target_os = "solaris"))]
 	post_process_fds(&ancillary_reader);
-	Ok((msg_info, ancillary_reader))
+	ancillary_reader
+}
+
+/// Get the size of the next message without consuming it.
+///
+/// On Linux and Android, `MSG_TRUNC` makes `recvmsg` report the full size of the message, even with an empty buffer.
+#[cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
+pub fn next_message_len(socket: &FileDesc) -> std::io::Result<usize> {
+	// Without an ancillary buffer, the kernel does not install any file descriptors from the peeked message.
+	let (info, _ancillary) = recv_msg(socket, &mut [], &mut [], libc::MSG_PEEK | libc::MSG_TRUNC)?;
+	Ok(info.bytes_read)
+}
+
+/// Get the size of the next message without consuming it.
+///
+/// Other platforms only use `MSG_TRUNC` as output flag, so we peek with a growing buffer until the message is no longer truncated.
+/// A message can not be larger than the receive buffer of the socket, so the buffer never grows beyond that.
+#[cfg(all(feature = "tokio", not(any(target_os = "linux", target_os = "android"))))]
+pub fn next_message_len(socket: &FileDesc) -> std::io::Result<usize> {
+	let max_len = get_buffer_size(socket, libc::SO_RCVBUF)?.max(1024);
+	let mut buffer = vec![0; 1024];
+	loop {
+		let (info, _ancillary) = recv_msg(socket, &mut [IoSliceMut::new(&mut buffer)], &mut [], libc::MSG_PEEK)?;
+		if !info.truncated {
+			return Ok(info.bytes_read);
+		}
+		if buffer.len() >= max_len {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				"message is larger than the receive buffer of the socket",
+			));
+		}
+		buffer.resize((buffer.len() * 2).min(max_len), 0);
+	}
+}
+
+#[cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+pub fn send_mmsg(socket: &FileDesc, entries: &[crate::SendBatchEntry]) -> std::io::Result<usize> {
+	let mut iovecs: Vec<libc::iovec> = entries
+		.iter()
+		.map(|entry| libc::iovec {
+			iov_base: entry.data.as_ptr() as *mut c_void,
+			iov_len: entry.data.len(),
+		})
+		.collect();
+
+	let mut headers: Vec<libc::mmsghdr> = Vec::with_capacity(entries.len());
+	for (entry, iovec) in entries.iter().zip(&mut iovecs) {
+		let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+		header.msg_hdr.msg_iov = iovec;
+		header.msg_hdr.msg_iovlen = 1;
+		if !entry.control.is_empty() {
+			header.msg_hdr.msg_control = entry.control.as_ptr() as *mut c_void;
+			header.msg_hdr.msg_controllen = entry.control.len() as _;
+		}
+		headers.push(header);
+	}
+
+	let len = headers.len().try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+	unsafe {
+		let sent = check(libc::sendmmsg(
+			socket.as_raw_fd(),
+			headers.as_mut_ptr(),
+			len,
+			SEND_MSG_DEFAULT_FLAGS as _,
+		))?;
+		Ok(sent as usize)
+	}
+}
+
+#[cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+pub fn recv_mmsg(socket: &FileDesc, entries: &mut [crate::RecvBatchEntry]) -> std::io::Result<usize> {
+	for entry in entries.iter_mut() {
+		entry.reset();
+	}
+
+	let mut iovecs: Vec<libc::iovec> = entries
+		.iter_mut()
+		.map(|entry| libc::iovec {
+			iov_base: entry.buffer.as_mut_ptr() as *mut c_void,
+			iov_len: entry.buffer.len(),
+		})
+		.collect();
+
+	let mut headers: Vec<libc::mmsghdr> = Vec::with_capacity(entries.len());
+	for (entry, iovec) in entries.iter_mut().zip(&mut iovecs) {
+		let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+		header.msg_hdr.msg_iov = iovec;
+		header.msg_hdr.msg_iovlen = 1;
+		if !entry.ancillary_buffer.is_empty() {
+			header.msg_hdr.msg_control = entry.ancillary_buffer.as_mut_ptr() as *mut c_void;
+			header.msg_hdr.msg_controllen = entry.ancillary_buffer.len() as _;
+		}
+		headers.push(header);
+	}
+
+	let len = headers.len().try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+	// MSG_WAITFORONE: return the messages that are available once we received one, instead of waiting to fill the batch.
+	let received = unsafe {
+		check(libc::recvmmsg(
+			socket.as_raw_fd(),
+			headers.as_mut_ptr(),
+			len,
+			(RECV_MSG_DEFAULT_FLAGS | libc::MSG_WAITFORONE) as _,
+			std::ptr::null_mut(),
+		))? as usize
+	};
+
+	// This is not a no-op on all platforms.
+	#[allow(clippy::unnecessary_cast)]
+	for (entry, header) in entries.iter_mut().zip(&headers).take(received) {
+		entry.info = Some(MessageInfo {
+			bytes_read: header.msg_len as usize,
+			truncated: header.msg_hdr.msg_flags & libc::MSG_TRUNC != 0,
+			ancillary_truncated: header.msg_hdr.msg_flags & libc::MSG_CTRUNC != 0,
+		});
+		entry.ancillary_len = header.msg_hdr.msg_controllen as usize;
+	}
+
+	Ok(received)
 }
 
 // Illumos and solaris do not support MSG_CMSG_CLOEXEC,
@@ -269,20 +624,33 @@ fn post_process_fds(ancillary: &AncillaryMessageReader) {
 	}
 }
 
-fn path_to_sockaddr(path: &Path) -> std::io::Result<(libc::sockaddr_un, usize)> {
+/// Convert a socket address to a `sockaddr_un` and the length of the used part.
+pub fn socket_addr_to_sockaddr(address: &SocketAddr) -> std::io::Result<(libc::sockaddr_un, usize)> {
 	use std::os::unix::ffi::OsStrExt;
-	let path = path.as_os_str().as_bytes();
+
+	let (prefix, name): (&[u8], &[u8]) = if let Some(path) = address.as_pathname() {
+		(&[], path.as_os_str().as_bytes())
+	} else if let Some(name) = address.as_abstract_name() {
+		(&[0], name)
+	} else {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			"can not bind or connect to an unnamed address",
+		));
+	};
+
 	unsafe {
 		let mut sockaddr: libc::sockaddr_un = core::mem::zeroed();
-		let abstract_path = cfg!(any(target_os = "linux", target_os = "android")) && path.first() == Some(&0);
 		// Abstract UNIX socket paths don't need a trailing zero byte
+		let abstract_path = !prefix.is_empty();
 		let max_len = if abstract_path {
 			core::mem::size_of_val(&sockaddr.sun_path)
 		} else {
 			core::mem::size_of_val(&sockaddr.sun_path) - 1
 		};
 
-		if path.len() > max_len {
+		let len = prefix.len() + name.len();
+		if len > max_len {
 			return Err(std::io::Error::new(
 				std::io::ErrorKind::InvalidInput,
 				"path length exceeds maximum sockaddr length",
@@ -290,47 +658,57 @@ fn path_to_sockaddr(path: &Path) -> std::io::Result<(libc::sockaddr_un, usize)>
 		}
 
 		sockaddr.sun_family = libc::AF_UNIX as _;
-		core::ptr::copy_nonoverlapping(path.as_ptr(), sockaddr.sun_path.as_mut_ptr() as *mut u8, path.len());
+		let sun_path = sockaddr.sun_path.as_mut_ptr() as *mut u8;
+		core::ptr::copy_nonoverlapping(prefix.as_ptr(), sun_path, prefix.len());
+		core::ptr::copy_nonoverlapping(name.as_ptr(), sun_path.add(prefix.len()), name.len());
 		if !abstract_path {
-			sockaddr.sun_path[path.len()] = 0;
+			sockaddr.sun_path[len] = 0;
 		}
 		let path_offset = sockaddr.sun_path.as_ptr() as usize - (&sockaddr as *const _ as usize);
 
 		// Do not add trailing zero byte to abstract UNIX socket paths on Linux and Android.
 		if abstract_path {
-			Ok((sockaddr, path_offset + path.len()))
+			Ok((sockaddr, path_offset + len))
 		} else {
-			Ok((sockaddr, path_offset + path.len() + 1))
+			Ok((sockaddr, path_offset + len + 1))
 		}
 	}
 }
 
-/// Get the Unix path of a socket address.
+/// Convert a `sockaddr_un` to a socket address.
 ///
-/// An error is returned if the address is not a Unix address, or if it is an unnamed or abstract.
-fn sockaddr_to_path(address: &libc::sockaddr_un, len: libc::socklen_t) -> std::io::Result<&std::path::Path> {
+/// An error is returned if the address is not a Unix address.
+fn sockaddr_to_socket_addr(address: &libc::sockaddr_un, len: libc::socklen_t) -> std::io::Result<SocketAddr> {
 	use std::ffi::OsStr;
 	use std::os::unix::ffi::OsStrExt;
 
-	if address.sun_family != libc::AF_LOCAL as _ {
-		Err(std::io::Error::new(
+	let path_offset = address.sun_path.as_ptr() as usize - (address as *const _ as usize);
+	if (len as usize) < path_offset {
+		// Some platforms report unnamed addresses with a zero length, without filling in the address family.
+		return Ok(SocketAddr::unnamed());
+	}
+
+	// Spell out the type: with the `json` feature, the `PartialEq` impls of `serde_json` make `as _` ambiguous.
+	if address.sun_family != libc::AF_LOCAL as libc::sa_family_t {
+		return Err(std::io::Error::new(
 			std::io::ErrorKind::InvalidData,
 			format!("address family is not AF_LOCAL/UNIX: {}", address.sun_family),
-		))
+		));
+	}
+
+	let path_len = (len as usize - path_offset).min(core::mem::size_of_val(&address.sun_path));
+	// SAFETY: `path_len` is clamped to the size of `sun_path`.
+	let path = unsafe { core::slice::from_raw_parts(address.sun_path.as_ptr().cast::<u8>(), path_len) };
+
+	if cfg!(any(target_os = "linux", target_os = "android")) && path.first() == Some(&0) {
+		// Do not strip trailing zero bytes from abstract UNIX socket paths on Linux and Android.
+		Ok(SocketAddr::abstract_name(path[1..].to_vec()))
 	} else {
-		unsafe {
-			let address: &libc::sockaddr_un = std::mem::transmute(address);
-			let sun_path: *const u8 = address.sun_path.as_ptr().cast();
-			let offset = sun_path.offset_from(address as *const _ as *const u8);
-			let path = core::slice::from_raw_parts(sun_path, len as usize - offset as usize);
-
-			// Do not strip trailing zero byte from abstract UNIX socket paths on Linux and Android.
-			if cfg!(any(target_os = "linux", target_os = "android")) && path.first() == Some(&0) {
-				Ok(Path::new(OsStr::from_bytes(path)))
-			} else {
-				let path = path.strip_suffix(&[0]).unwrap_or(path);
-				Ok(Path::new(OsStr::from_bytes(path)))
-			}
+		let path = path.split(|&byte| byte == 0).next().unwrap_or(&[]);
+		if path.is_empty() {
+			Ok(SocketAddr::unnamed())
+		} else {
+			Ok(SocketAddr::pathname(OsStr::from_bytes(path).into()))
 		}
 	}
 }
@@ -0,0 +1,129 @@
+use filedesc::FileDesc;
+use std::os::raw::c_int;
+use std::sync::Mutex;
+
+use crate::sys;
+
+/// The first file descriptor passed by systemd.
+const LISTEN_FDS_START: c_int = 3;
+
+/// File descriptors that have already been taken by a previous call.
+static CLAIMED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+/// Take ownership of the seqpacket listeners passed in by systemd.
+///
+/// See `man 3 sd_listen_fds` for a description of the protocol.
+///
+/// If `name` is given, only file descriptors with a matching name in `LISTEN_FDNAMES` are taken.
+///
+/// Returns an empty list if the process was not socket activated,
+/// or if all matching file descriptors have already been taken.
+/// Returns an error if any of the matching file descriptors is not a listening `AF_UNIX` / `SOCK_SEQPACKET` socket.
+/// In that case, no file descriptors are taken.
+pub(crate) fn take_listen_fds(name: Option<&str>) -> std::io::Result<Vec<FileDesc>> {
+	let count = match listen_fds_count()? {
+		Some(count) => count,
+		None => return Ok(Vec::new()),
+	};
+	let names = listen_fd_names(count)?;
+
+	let mut claimed = CLAIMED.lock().unwrap_or_else(|e| e.into_inner());
+	let fds: Vec<c_int> = (0..count)
+		.filter(|&i| name.is_none() || names.as_ref().map(|names| names[i].as_str()) == name)
+		.map(|i| LISTEN_FDS_START + i as c_int)
+		.filter(|fd| !claimed.contains(fd))
+		.collect();
+
+	for &fd in &fds {
+		// SAFETY: The `FileDesc` is wrapped in a `ManuallyDrop`, so we never close the file descriptor here.
+		// If the file descriptor is not open, the checks simply fail with `EBADF`.
+		let socket = core::mem::ManuallyDrop::new(unsafe { FileDesc::from_raw_fd(fd) });
+		check_listener(&socket).map_err(|e| {
+			std::io::Error::new(
+				e.kind(),
+				format!("invalid file descriptor {fd} passed by the service manager: {e}"),
+			)
+		})?;
+	}
+
+	let mut sockets = Vec::with_capacity(fds.len());
+	for fd in fds {
+		claimed.push(fd);
+		// SAFETY: The service manager passed ownership of the file descriptor to this process,
+		// and we keep track of claimed file descriptors so we only take ownership once.
+		let mut socket = unsafe { FileDesc::from_raw_fd(fd) };
+		socket.set_close_on_exec(true)?;
+		// SAFETY: We now own the file descriptor, nobody else should be modifying its flags.
+		unsafe {
+			sys::set_non_blocking(&mut socket, true)?;
+		}
+		sockets.push(socket);
+	}
+	Ok(sockets)
+}
+
+/// Get the number of passed file descriptors, if they are meant for this process.
+fn listen_fds_count() -> std::io::Result<Option<usize>> {
+	let pid = match std::env::var("LISTEN_PID") {
+		Ok(pid) => pid,
+		Err(std::env::VarError::NotPresent) => return Ok(None),
+		Err(std::env::VarError::NotUnicode(_)) => return Err(invalid_env("LISTEN_PID")),
+	};
+	let pid: u32 = pid.parse().map_err(|_| invalid_env("LISTEN_PID"))?;
+	if pid != std::process::id() {
+		return Ok(None);
+	}
+
+	let count = match std::env::var("LISTEN_FDS") {
+		Ok(count) => count,
+		Err(std::env::VarError::NotPresent) => return Ok(None),
+		Err(std::env::VarError::NotUnicode(_)) => return Err(invalid_env("LISTEN_FDS")),
+	};
+	let count: usize = count.parse().map_err(|_| invalid_env("LISTEN_FDS"))?;
+	if count > (c_int::MAX - LISTEN_FDS_START) as usize {
+		return Err(invalid_env("LISTEN_FDS"));
+	}
+	Ok(Some(count))
+}
+
+/// Get the names of the passed file descriptors, if the service manager provided them.
+fn listen_fd_names(count: usize) -> std::io::Result<Option<Vec<String>>> {
+	let names = match std::env::var("LISTEN_FDNAMES") {
+		Ok(names) => names,
+		Err(std::env::VarError::NotPresent) => return Ok(None),
+		Err(std::env::VarError::NotUnicode(_)) => return Err(invalid_env("LISTEN_FDNAMES")),
+	};
+	let names: Vec<String> = names.split(':').map(String::from).collect();
+	if names.len() != count {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			format!(
+				"LISTEN_FDNAMES contains {} names, but LISTEN_FDS is {count}",
+				names.len()
+			),
+		));
+	}
+	Ok(Some(names))
+}
+
+/// Check that a file descriptor is a listening `AF_UNIX` / `SOCK_SEQPACKET` socket.
+fn check_listener(socket: &FileDesc) -> std::io::Result<()> {
+	let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+	if sys::get_socket_family(socket)? != libc::AF_UNIX {
+		return Err(invalid("not an AF_UNIX socket"));
+	}
+	if sys::get_int_option(socket, libc::SOL_SOCKET, libc::SO_TYPE)? != libc::SOCK_SEQPACKET {
+		return Err(invalid("not a SOCK_SEQPACKET socket"));
+	}
+	if !sys::get_bool_option(socket, libc::SO_ACCEPTCONN)? {
+		return Err(invalid("not a listening socket"));
+	}
+	Ok(())
+}
+
+fn invalid_env(name: &str) -> std::io::Error {
+	std::io::Error::new(
+		std::io::ErrorKind::InvalidData,
+		format!("invalid value for environment variable {name}"),
+	)
+}
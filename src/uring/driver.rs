@@ -0,0 +1,312 @@
+use io_uring::{cqueue, opcode, squeue, IoUring};
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use tokio::io::unix::AsyncFd;
+
+/// Number of submission queue entries of each ring.
+const RING_ENTRIES: u32 = 64;
+
+/// User data for cancellation requests, which we don't track.
+const CANCEL_USER_DATA: u64 = u64::MAX;
+
+/// The owned data of an in-flight operation.
+///
+/// The kernel may access the data until the operation completes,
+/// so the driver keeps it alive until then, even if the future waiting for it is dropped.
+pub(crate) trait OpData: Send + 'static {
+	/// Convert the data into [`Any`] to downcast it back to the concrete type.
+	fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+	/// Clean up after an operation that completed after the waiting future was dropped.
+	fn abandon(self: Box<Self>, _result: i32) {}
+}
+
+/// Driver for a single io_uring instance.
+///
+/// The ring file descriptor is registered with the tokio runtime.
+/// It becomes readable when completion queue entries are available.
+/// Any task waiting for an operation reaps all available completions and wakes up the tasks waiting for them.
+pub(crate) struct Driver {
+	io: AsyncFd<RingFd>,
+	state: Mutex<State>,
+}
+
+/// The raw file descriptor of the ring, owned by the [`IoUring`] in the driver state.
+struct RingFd(RawFd);
+
+impl AsRawFd for RingFd {
+	fn as_raw_fd(&self) -> RawFd {
+		self.0
+	}
+}
+
+struct State {
+	ring: IoUring,
+	ops: HashMap<u64, Op>,
+	next_id: u64,
+}
+
+struct Op {
+	lifecycle: Lifecycle,
+	data: Box<dyn OpData>,
+}
+
+enum Lifecycle {
+	/// The operation is submitted, and a future may be waiting for it.
+	Submitted(Option<Waker>),
+
+	/// The operation completed, but the future did not pick up the result yet.
+	Completed(i32),
+
+	/// The future waiting for the operation was dropped.
+	Abandoned,
+}
+
+impl Driver {
+	/// Create a new io_uring instance and register it with the tokio runtime.
+	pub(crate) fn new() -> std::io::Result<Arc<Self>> {
+		let ring = IoUring::new(RING_ENTRIES)?;
+		let io = AsyncFd::with_interest(RingFd(ring.as_raw_fd()), tokio::io::Interest::READABLE)?;
+		let state = State {
+			ring,
+			ops: HashMap::new(),
+			next_id: 0,
+		};
+		Ok(Arc::new(Self {
+			io,
+			state: Mutex::new(state),
+		}))
+	}
+
+	/// Submit an operation and wait for it to complete.
+	///
+	/// Returns the result of the operation and the operation data.
+	/// A negative result from the kernel is converted to an error.
+	///
+	/// If the returned future is dropped before the operation completes,
+	/// the operation is cancelled and the data is kept alive until the kernel is done with it.
+	/// The data is also not returned if waiting for the completion fails.
+	///
+	/// # Safety
+	/// The entry may only refer to memory owned by `data`, and that memory must remain valid when `data` is moved.
+	pub(crate) async unsafe fn run<T: OpData>(
+		&self,
+		entry: squeue::Entry,
+		data: Box<T>,
+	) -> (std::io::Result<usize>, Option<Box<T>>) {
+		let id = match self.submit(entry, data) {
+			Ok(id) => id,
+			Err((e, data)) => return (Err(e), Some(data)),
+		};
+		let mut guard = CancelOnDrop {
+			driver: self,
+			id: Some(id),
+		};
+
+		loop {
+			let readable = self.io.readable();
+			let mut readable = std::pin::pin!(readable);
+			let event = std::future::poll_fn(|cx| {
+				if let Poll::Ready(result) = self.poll_op(cx, id) {
+					return Poll::Ready(Ok(Some(result)));
+				}
+				readable.as_mut().poll(cx).map_ok(|mut ready_guard| {
+					ready_guard.clear_ready();
+					None
+				})
+			})
+			.await;
+
+			match event {
+				Err(e) => return (Err(e), None),
+				Ok(None) => continue,
+				Ok(Some((result, data))) => {
+					guard.id = None;
+					let data = data
+						.into_any()
+						.downcast::<T>()
+						.expect("operation data has the wrong type");
+					let result = match usize::try_from(result) {
+						Ok(result) => Ok(result),
+						Err(_) => Err(std::io::Error::from_raw_os_error(-result)),
+					};
+					return (result, Some(data));
+				},
+			}
+		}
+	}
+
+	/// Push an entry on the submission queue and submit it to the kernel.
+	///
+	/// If the entry can not be submitted, the data is returned with the error.
+	///
+	/// # Safety
+	/// The entry may only refer to memory owned by `data`, and that memory must remain valid when `data` is moved.
+	unsafe fn submit<T: OpData>(&self, entry: squeue::Entry, data: Box<T>) -> Result<u64, (std::io::Error, Box<T>)> {
+		let mut state = self.lock();
+		let id = state.next_id;
+		state.next_id = (state.next_id + 1) % CANCEL_USER_DATA;
+		let entry = entry.user_data(id);
+
+		// SAFETY: The caller guarantees the entry only refers to memory owned by `data`,
+		// and we keep `data` alive until the operation completes.
+		if let Err(e) = state.push(&entry) {
+			return Err((e, data));
+		}
+		state.ops.insert(
+			id,
+			Op {
+				lifecycle: Lifecycle::Submitted(None),
+				data,
+			},
+		);
+		Ok(id)
+	}
+
+	/// Check if an operation completed, or register the waker of the current task.
+	fn poll_op(&self, cx: &mut Context, id: u64) -> Poll<(i32, Box<dyn OpData>)> {
+		let mut state = self.lock();
+		state.reap();
+		// Only `run()` polls, for an operation it submitted itself.
+		// The operation stays in the map until it is removed here, or until `cancel()` runs when `run()` stops polling.
+		let op = state.ops.get_mut(&id).expect("unknown operation");
+		match &mut op.lifecycle {
+			Lifecycle::Completed(result) => {
+				let result = *result;
+				let op = state.ops.remove(&id).expect("operation disappeared");
+				Poll::Ready((result, op.data))
+			},
+			Lifecycle::Submitted(waker) => {
+				*waker = Some(cx.waker().clone());
+				Poll::Pending
+			},
+			Lifecycle::Abandoned => unreachable!("polled an abandoned operation"),
+		}
+	}
+
+	/// Cancel an operation because the future waiting for it was dropped.
+	fn cancel(&self, id: u64) {
+		let mut state = self.lock();
+		state.reap();
+		let Some(op) = state.ops.get_mut(&id) else { return };
+		match op.lifecycle {
+			Lifecycle::Completed(result) => {
+				let op = state.ops.remove(&id).expect("operation disappeared");
+				op.data.abandon(result);
+			},
+			Lifecycle::Submitted(_) | Lifecycle::Abandoned => {
+				op.lifecycle = Lifecycle::Abandoned;
+				let entry = opcode::AsyncCancel::new(id).build().user_data(CANCEL_USER_DATA);
+				// SAFETY: A cancel request does not refer to any memory.
+				// If submitting the cancellation fails, the operation simply runs to completion.
+				unsafe {
+					state.push(&entry).ok();
+				}
+			},
+		}
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+		self.state.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+impl State {
+	/// Push an entry on the submission queue and submit it.
+	///
+	/// Returns an error if the entry could not be pushed on the queue.
+	/// Once the entry is on the queue, the kernel may start using the memory it refers to at any time.
+	///
+	/// # Safety
+	/// The entry must only refer to memory that remains valid until the operation completes.
+	unsafe fn push(&mut self, entry: &squeue::Entry) -> std::io::Result<()> {
+		if self.ring.submission().push(entry).is_err() {
+			// The queue is full, submit the pending entries to make room.
+			self.ring.submit()?;
+			self.ring
+				.submission()
+				.push(entry)
+				.map_err(|_| std::io::Error::other("io_uring submission queue is full"))?;
+		}
+		// If submitting fails now, the entry stays on the queue and is submitted when we reap completions.
+		self.ring.submit().ok();
+		Ok(())
+	}
+
+	/// Process all available completion queue entries.
+	fn reap(&mut self) {
+		if !self.ring.submission().is_empty() {
+			self.ring.submit().ok();
+		}
+		let completions: Vec<cqueue::Entry> = self.ring.completion().collect();
+		for completion in completions {
+			let id = completion.user_data();
+			if id == CANCEL_USER_DATA {
+				continue;
+			}
+			let Some(op) = self.ops.get_mut(&id) else { continue };
+			match std::mem::replace(&mut op.lifecycle, Lifecycle::Completed(completion.result())) {
+				Lifecycle::Submitted(Some(waker)) => waker.wake(),
+				Lifecycle::Submitted(None) => (),
+				Lifecycle::Completed(_) => unreachable!("operation completed twice"),
+				Lifecycle::Abandoned => {
+					let op = self.ops.remove(&id).expect("operation disappeared");
+					op.data.abandon(completion.result());
+				},
+			}
+		}
+	}
+}
+
+impl Drop for State {
+	fn drop(&mut self) {
+		// The kernel may still use the memory of in-flight operations, so cancel them and wait for them to finish.
+		let ids: Vec<u64> = self.ops.keys().copied().collect();
+		for id in ids {
+			let entry = opcode::AsyncCancel::new(id).build().user_data(CANCEL_USER_DATA);
+			// SAFETY: A cancel request does not refer to any memory.
+			unsafe {
+				self.push(&entry).ok();
+			}
+		}
+		while self
+			.ops
+			.values()
+			.any(|op| !matches!(op.lifecycle, Lifecycle::Completed(_)))
+		{
+			// Blocking here is unavoidable: the kernel may still write to the buffers of the operations,
+			// so we can not free them before they complete. They were cancelled above, so this should not take long.
+			if self.ring.submit_and_wait(1).is_err() {
+				// We can not wait for the operations, so we have to leak their data.
+				for (_id, op) in self.ops.drain() {
+					std::mem::forget(op.data);
+				}
+				return;
+			}
+			self.reap();
+		}
+		for (_id, op) in self.ops.drain() {
+			if let Lifecycle::Completed(result) = op.lifecycle {
+				op.data.abandon(result);
+			}
+		}
+	}
+}
+
+/// Cancels an operation when the future waiting for it is dropped.
+struct CancelOnDrop<'a> {
+	driver: &'a Driver,
+	id: Option<u64>,
+}
+
+impl Drop for CancelOnDrop<'_> {
+	fn drop(&mut self) {
+		if let Some(id) = self.id {
+			self.driver.cancel(id);
+		}
+	}
+}
@@ -0,0 +1,491 @@
+use filedesc::FileDesc;
+use io_uring::{opcode, types};
+use std::any::Any;
+use std::os::raw::c_int;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::sync::Arc;
+
+use crate::ancillary::{AncillaryMessageReader, AncillaryMessageWriter};
+use crate::{sys, MessageInfo, SocketAddr, ToSocketAddr, UCred};
+
+mod driver;
+use driver::{Driver, OpData};
+
+/// Unix seqpacket socket that sends and receives messages with io_uring.
+///
+/// Instead of waiting for readiness and then doing a system call,
+/// this type submits `IORING_OP_SENDMSG` and `IORING_OP_RECVMSG` operations to an io_uring instance owned by the socket.
+/// The completions are processed by the tokio runtime.
+///
+/// Because the kernel accesses the data buffer while an operation is in flight, the I/O functions take ownership of the buffer,
+/// and give it back when the operation completes.
+/// Ancillary data is written with an [`AncillaryMessageWriter`] and read with an [`AncillaryMessageReader`], just like with [`crate::UnixSeqpacket`].
+/// The control data is copied to and from memory owned by the operation,
+/// and file descriptors being sent are duplicated until the operation completes.
+///
+/// If a future is dropped before the operation completes, the operation is cancelled.
+/// Note that the operation may complete anyway: a message may still be sent, or a received message may be discarded.
+/// File descriptors in discarded messages are closed.
+///
+/// Every socket owns a separate io_uring instance with 64 submission queue entries,
+/// which is registered with the tokio runtime.
+/// That costs an extra file descriptor and the memory of the ring for each socket,
+/// so this type is meant for a small number of connections with a high message rate, not for thousands of connections.
+///
+/// This type is only available on Linux with the `uring` feature.
+pub struct UringSeqpacket {
+	// Dropped first, so in-flight operations are finished before the socket is closed.
+	driver: Arc<Driver>,
+	socket: FileDesc,
+}
+
+impl std::fmt::Debug for UringSeqpacket {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("UringSeqpacket")
+			.field("fd", &self.socket.as_raw_fd())
+			.finish()
+	}
+}
+
+impl AsFd for UringSeqpacket {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.socket.as_fd()
+	}
+}
+
+impl TryFrom<OwnedFd> for UringSeqpacket {
+	type Error = std::io::Error;
+
+	/// Wrap a file descriptor as [`UringSeqpacket`].
+	///
+	/// The file descriptor is put in blocking mode, because io_uring reports `EAGAIN` for non-blocking sockets instead of waiting.
+	fn try_from(fd: OwnedFd) -> std::io::Result<Self> {
+		let mut socket = FileDesc::new(fd);
+		// SAFETY: We own the socket, so nobody else should be modifying its flags right now.
+		unsafe {
+			sys::set_non_blocking(&mut socket, false)?;
+		}
+		Self::new(socket)
+	}
+}
+
+impl TryFrom<crate::UnixSeqpacket> for UringSeqpacket {
+	type Error = std::io::Error;
+
+	/// Convert a [`crate::UnixSeqpacket`] into a [`UringSeqpacket`].
+	fn try_from(socket: crate::UnixSeqpacket) -> std::io::Result<Self> {
+		Self::try_from(OwnedFd::from(socket))
+	}
+}
+
+impl From<UringSeqpacket> for OwnedFd {
+	/// Wait for in-flight operations to finish and return the inner file descriptor.
+	///
+	/// The file descriptor is left in blocking mode.
+	fn from(socket: UringSeqpacket) -> Self {
+		let UringSeqpacket { driver, socket } = socket;
+		drop(driver);
+		socket.into_fd()
+	}
+}
+
+impl UringSeqpacket {
+	/// Wrap a socket in blocking mode with a new io_uring instance.
+	fn new(socket: FileDesc) -> std::io::Result<Self> {
+		let driver = Driver::new()?;
+		Ok(Self { driver, socket })
+	}
+
+	/// Connect a new seqpacket socket to the given address.
+	pub async fn connect<A: ToSocketAddr>(address: A) -> std::io::Result<Self> {
+		let socket = crate::UnixSeqpacket::connect(address).await?;
+		Self::try_from(socket)
+	}
+
+	/// Create a pair of connected seqpacket sockets.
+	pub fn pair() -> std::io::Result<(Self, Self)> {
+		let (a, b) = sys::local_seqpacket_pair_blocking()?;
+		Ok((Self::new(a)?, Self::new(b)?))
+	}
+
+	/// Get the raw file descriptor of the socket.
+	pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.socket.as_raw_fd()
+	}
+
+	/// Get the address of the local half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::local_addr()`] for more information.
+	pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_local_address(&self.socket)
+	}
+
+	/// Get the address of the remote half of this connection.
+	///
+	/// See [`crate::UnixSeqpacket::peer_addr()`] for more information.
+	pub fn peer_addr(&self) -> std::io::Result<SocketAddr> {
+		sys::get_peer_address(&self.socket)
+	}
+
+	/// Get the effective credentials of the process which called `connect` or `pair`.
+	///
+	/// Note that this is not necessarily the process that currently has the file descriptor of the other side of the connection.
+	pub fn peer_cred(&self) -> std::io::Result<UCred> {
+		UCred::from_socket_peer(&self.socket)
+	}
+
+	/// Get and clear the value of the `SO_ERROR` option.
+	pub fn take_error(&self) -> std::io::Result<Option<std::io::Error>> {
+		sys::take_socket_error(&self.socket)
+	}
+
+	/// Send data on the socket to the connected peer.
+	///
+	/// The buffer is returned when the operation completes, together with the result.
+	pub async fn send(&self, buffer: Vec<u8>) -> (std::io::Result<usize>, Vec<u8>) {
+		self.send_with_ancillary(buffer, &mut AncillaryMessageWriter::new(&mut []))
+			.await
+	}
+
+	/// Send data from multiple buffers on the socket to the connected peer.
+	///
+	/// The buffers are sent as a single message.
+	/// They are returned when the operation completes, together with the result.
+	pub async fn send_vectored(&self, buffers: Vec<Vec<u8>>) -> (std::io::Result<usize>, Vec<Vec<u8>>) {
+		self.send_vectored_with_ancillary(buffers, &mut AncillaryMessageWriter::new(&mut []))
+			.await
+	}
+
+	/// Send data with ancillary data on the socket to the connected peer.
+	///
+	/// The control data is copied from the ancillary writer before the operation is submitted,
+	/// so the writer is not used after this function returns.
+	///
+	/// The buffer is returned when the operation completes, together with the result.
+	pub async fn send_with_ancillary(
+		&self,
+		buffer: Vec<u8>,
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> (std::io::Result<usize>, Vec<u8>) {
+		let (result, buffers) = self.send_vectored_with_ancillary(vec![buffer], ancillary).await;
+		(result, buffers.into_iter().next().unwrap_or_default())
+	}
+
+	/// Send data from multiple buffers with ancillary data on the socket to the connected peer.
+	///
+	/// The buffers are sent as a single message.
+	/// The control data is copied from the ancillary writer before the operation is submitted,
+	/// so the writer is not used after this function returns.
+	///
+	/// The buffers are returned when the operation completes, together with the result.
+	pub async fn send_vectored_with_ancillary(
+		&self,
+		buffers: Vec<Vec<u8>>,
+		ancillary: &mut AncillaryMessageWriter<'_>,
+	) -> (std::io::Result<usize>, Vec<Vec<u8>>) {
+		let control = &ancillary.buffer[..ancillary.length];
+		let op = match SendMsg::new(buffers, control) {
+			Ok(op) => op,
+			Err((e, buffers)) => return (Err(e), buffers),
+		};
+		let entry = opcode::SendMsg::new(types::Fd(self.socket.as_raw_fd()), &op.header)
+			.flags(sys::SEND_MSG_DEFAULT_FLAGS as u32)
+			.build();
+
+		// SAFETY: The entry only refers to the header in `op`, which refers to the buffers in `op`.
+		// All of them are heap allocated, so they do not move when `op` is moved.
+		let (result, op) = unsafe { self.driver.run(entry, op).await };
+		let buffers = op.map(|op| op.buffers).unwrap_or_default();
+		(result, buffers)
+	}
+
+	/// Receive data on the socket from the connected peer.
+	///
+	/// The message is written to the start of the buffer, using the full capacity of the buffer.
+	/// When the operation completes, the length of the buffer is set to the number of bytes received,
+	/// and the buffer is returned together with the result.
+	pub async fn recv(&self, buffer: Vec<u8>) -> (std::io::Result<MessageInfo>, Vec<u8>) {
+		let (result, buffer) = self.recv_with_ancillary(buffer, &mut []).await;
+		(result.map(|(info, _ancillary)| info), buffer)
+	}
+
+	/// Receive data into multiple buffers on the socket from the connected peer.
+	///
+	/// The message is scattered over the buffers in order, using the full capacity of each buffer.
+	/// When the operation completes, the length of each buffer is set to the number of bytes received in it,
+	/// and the buffers are returned together with the result.
+	pub async fn recv_vectored(&self, buffers: Vec<Vec<u8>>) -> (std::io::Result<MessageInfo>, Vec<Vec<u8>>) {
+		let (result, buffers) = self.recv_vectored_with_ancillary(buffers, &mut []).await;
+		(result.map(|(info, _ancillary)| info), buffers)
+	}
+
+	/// Receive data with ancillary data on the socket from the connected peer.
+	///
+	/// The message is written to the start of the buffer, using the full capacity of the buffer.
+	/// When the operation completes, the length of the buffer is set to the number of bytes received,
+	/// and the buffer is returned together with the result.
+	///
+	/// The control data is copied into the ancillary buffer when the operation completes.
+	/// Any file descriptors received in the ancillary data will have the `close-on-exec` flag set.
+	pub async fn recv_with_ancillary<'a>(
+		&self,
+		buffer: Vec<u8>,
+		ancillary_buffer: &'a mut [u8],
+	) -> (std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>, Vec<u8>) {
+		let (result, buffers) = self.recv_vectored_with_ancillary(vec![buffer], ancillary_buffer).await;
+		(result, buffers.into_iter().next().unwrap_or_default())
+	}
+
+	/// Receive data into multiple buffers with ancillary data on the socket from the connected peer.
+	///
+	/// The message is scattered over the buffers in order, using the full capacity of each buffer.
+	/// When the operation completes, the length of each buffer is set to the number of bytes received in it,
+	/// and the buffers are returned together with the result.
+	///
+	/// The control data is copied into the ancillary buffer when the operation completes.
+	/// Any file descriptors received in the ancillary data will have the `close-on-exec` flag set.
+	pub async fn recv_vectored_with_ancillary<'a>(
+		&self,
+		buffers: Vec<Vec<u8>>,
+		ancillary_buffer: &'a mut [u8],
+	) -> (std::io::Result<(MessageInfo, AncillaryMessageReader<'a>)>, Vec<Vec<u8>>) {
+		let mut op = match RecvMsg::new(buffers, ancillary_buffer.len()) {
+			Ok(op) => op,
+			Err((e, buffers)) => return (Err(e), buffers),
+		};
+		let entry = opcode::RecvMsg::new(types::Fd(self.socket.as_raw_fd()), &mut op.header)
+			.flags(sys::RECV_MSG_DEFAULT_FLAGS as u32)
+			.build();
+
+		// SAFETY: The entry only refers to the header in `op`, which refers to the buffers in `op`.
+		// All of them are heap allocated, so they do not move when `op` is moved.
+		let (result, op) = unsafe { self.driver.run(entry, op).await };
+		let (bytes_read, mut op) = match (result, op) {
+			(Ok(bytes_read), Some(op)) => (bytes_read, op),
+			(Err(e), Some(op)) => return (Err(e), op.into_buffers(0)),
+			(Err(e), None) => return (Err(e), Vec::new()),
+			(Ok(_), None) => unreachable!("operation data is only lost on errors"),
+		};
+
+		let info = MessageInfo {
+			bytes_read,
+			truncated: op.header.msg_flags & libc::MSG_TRUNC != 0,
+			ancillary_truncated: op.header.msg_flags & libc::MSG_CTRUNC != 0,
+		};
+		let control = op.take_control();
+		let ancillary_buffer = &mut ancillary_buffer[..control.len()];
+		ancillary_buffer.copy_from_slice(control);
+		// SAFETY: The buffer contains the control messages received from the kernel,
+		// and we took them out of the operation, so nobody else owns the file descriptors.
+		let ancillary = unsafe { AncillaryMessageReader::new(ancillary_buffer, info.ancillary_truncated) };
+		(Ok((info, ancillary)), op.into_buffers(bytes_read))
+	}
+
+	/// Shuts down the read, write, or both halves of this connection.
+	///
+	/// This function will cause all pending and future I/O calls on the
+	/// specified portions to immediately return with an appropriate value
+	/// (see the documentation of `Shutdown`).
+	pub fn shutdown(&self, how: std::net::Shutdown) -> std::io::Result<()> {
+		sys::shutdown(&self.socket, how)
+	}
+}
+
+impl AsRawFd for UringSeqpacket {
+	fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+		self.as_raw_fd()
+	}
+}
+
+/// Data of an `IORING_OP_SENDMSG` operation.
+struct SendMsg {
+	header: libc::msghdr,
+	iovs: Vec<libc::iovec>,
+	buffers: Vec<Vec<u8>>,
+	control: Vec<u64>,
+	_fds: Vec<OwnedFd>,
+}
+
+// SAFETY: The raw pointers in the header only point to data owned by the operation itself.
+unsafe impl Send for SendMsg {}
+
+impl OpData for SendMsg {
+	fn into_any(self: Box<Self>) -> Box<dyn Any> {
+		self
+	}
+}
+
+impl SendMsg {
+	/// Create the operation data with a copy of the control data.
+	///
+	/// File descriptors in the control data are duplicated,
+	/// so they remain valid until the operation completes, even if the caller closes them.
+	fn new(mut buffers: Vec<Vec<u8>>, control: &[u8]) -> Result<Box<Self>, (std::io::Error, Vec<Vec<u8>>)> {
+		let iovs = buffers
+			.iter_mut()
+			.map(|buffer| libc::iovec {
+				iov_base: buffer.as_mut_ptr().cast(),
+				iov_len: buffer.len(),
+			})
+			.collect();
+		let mut op = Box::new(Self {
+			// SAFETY: All-zero is a valid value for `msghdr`.
+			header: unsafe { std::mem::zeroed() },
+			iovs,
+			buffers,
+			// Use `u64` for the control buffer to ensure it is aligned for `cmsghdr`.
+			control: vec![0; control.len().div_ceil(8)],
+			_fds: Vec::new(),
+		});
+
+		op.header.msg_iov = op.iovs.as_mut_ptr();
+		if let Err(e) = set_header_lengths(&mut op.header, op.iovs.len(), control.len()) {
+			return Err((e, std::mem::take(&mut op.buffers)));
+		}
+		if !control.is_empty() {
+			// SAFETY: The control buffer is at least `control.len()` bytes large.
+			unsafe {
+				std::ptr::copy_nonoverlapping(control.as_ptr(), op.control.as_mut_ptr().cast(), control.len());
+			}
+			op.header.msg_control = op.control.as_mut_ptr().cast();
+			// SAFETY: The header refers to the control messages copied from an `AncillaryMessageWriter`.
+			match unsafe { duplicate_fds(&op.header) } {
+				Ok(fds) => op._fds = fds,
+				Err(e) => return Err((e, std::mem::take(&mut op.buffers))),
+			}
+		}
+		Ok(op)
+	}
+}
+
+/// Data of an `IORING_OP_RECVMSG` operation.
+struct RecvMsg {
+	header: libc::msghdr,
+	iovs: Vec<libc::iovec>,
+	buffers: Vec<Vec<u8>>,
+	control: Vec<u64>,
+}
+
+// SAFETY: The raw pointers in the header only point to data owned by the operation itself.
+unsafe impl Send for RecvMsg {}
+
+impl OpData for RecvMsg {
+	fn into_any(self: Box<Self>) -> Box<dyn Any> {
+		self
+	}
+
+	fn abandon(mut self: Box<Self>, result: i32) {
+		if result >= 0 {
+			// Close any file descriptors we received.
+			let control = self.take_control();
+			// SAFETY: The buffer contains the control messages received from the kernel, and nobody else owns them.
+			drop(unsafe { AncillaryMessageReader::new(control, false) });
+		}
+	}
+}
+
+impl RecvMsg {
+	/// Create the operation data with a control buffer of the given size.
+	///
+	/// If the operation can not be created, the buffers are returned with the error.
+	fn new(mut buffers: Vec<Vec<u8>>, control_len: usize) -> Result<Box<Self>, (std::io::Error, Vec<Vec<u8>>)> {
+		let iovs = buffers
+			.iter_mut()
+			.map(|buffer| {
+				buffer.clear();
+				libc::iovec {
+					iov_base: buffer.as_mut_ptr().cast(),
+					iov_len: buffer.capacity(),
+				}
+			})
+			.collect();
+		let mut op = Box::new(Self {
+			// SAFETY: All-zero is a valid value for `msghdr`.
+			header: unsafe { std::mem::zeroed() },
+			iovs,
+			buffers,
+			// Use `u64` for the control buffer to ensure it is aligned for `cmsghdr`.
+			control: vec![0; control_len.div_ceil(8)],
+		});
+
+		op.header.msg_iov = op.iovs.as_mut_ptr();
+		if let Err(e) = set_header_lengths(&mut op.header, op.iovs.len(), control_len) {
+			return Err((e, std::mem::take(&mut op.buffers)));
+		}
+		if control_len > 0 {
+			op.header.msg_control = op.control.as_mut_ptr().cast();
+		}
+		Ok(op)
+	}
+
+	/// Take the received control messages out of the operation.
+	///
+	/// The caller takes ownership of the file descriptors in the control messages.
+	fn take_control(&mut self) -> &mut [u8] {
+		// This is not a no-op on all platforms.
+		#[allow(clippy::unnecessary_cast)]
+		let length = std::mem::take(&mut self.header.msg_controllen) as usize;
+		// SAFETY: The kernel never reports more control data than the size of the control buffer.
+		unsafe { std::slice::from_raw_parts_mut(self.control.as_mut_ptr().cast(), length) }
+	}
+
+	/// Get the data buffers with the given number of received bytes.
+	///
+	/// The received bytes fill the buffers in order.
+	fn into_buffers(mut self, bytes_read: usize) -> Vec<Vec<u8>> {
+		let mut remaining = bytes_read;
+		for buffer in &mut self.buffers {
+			// Never received more than the capacity of the buffer.
+			let length = remaining.min(buffer.capacity());
+			// SAFETY: The kernel initialized the first `length` bytes of the buffer.
+			unsafe {
+				buffer.set_len(length);
+			}
+			remaining -= length;
+		}
+		std::mem::take(&mut self.buffers)
+	}
+}
+
+/// Set the number of I/O vectors and the size of the control buffer in a message header.
+///
+/// The types of these fields differ between platforms, so this fails if a value does not fit.
+fn set_header_lengths(header: &mut libc::msghdr, iov_count: usize, control_len: usize) -> std::io::Result<()> {
+	// This is not a no-op on all platforms.
+	#[allow(clippy::useless_conversion)]
+	{
+		header.msg_iovlen = iov_count.try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+		header.msg_controllen = control_len.try_into().map_err(|_| std::io::ErrorKind::InvalidInput)?;
+	}
+	Ok(())
+}
+
+/// Duplicate all file descriptors in the `SCM_RIGHTS` messages of a message header.
+///
+/// The file descriptors in the control messages are replaced with the duplicates.
+///
+/// # Safety
+/// The header must refer to valid control messages.
+unsafe fn duplicate_fds(header: &libc::msghdr) -> std::io::Result<Vec<OwnedFd>> {
+	let mut fds = Vec::new();
+	let mut cmsg = libc::CMSG_FIRSTHDR(header);
+	while !cmsg.is_null() {
+		if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+			let data = libc::CMSG_DATA(cmsg).cast::<c_int>();
+			// This is not a no-op on all platforms.
+			#[allow(clippy::unnecessary_cast)]
+			let count = ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / std::mem::size_of::<c_int>();
+			for i in 0..count {
+				let fd = data.add(i).read_unaligned();
+				let duplicate = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+				if duplicate < 0 {
+					return Err(std::io::Error::last_os_error());
+				}
+				fds.push(OwnedFd::from_raw_fd(duplicate));
+				data.add(i).write_unaligned(duplicate);
+			}
+		}
+		cmsg = libc::CMSG_NXTHDR(header, cmsg);
+	}
+	Ok(fds)
+}
@@ -1,9 +1,9 @@
-#![cfg(any(target_os = "linux", target_os = "android"))]
+#![cfg(all(feature = "tokio", any(target_os = "linux", target_os = "android")))]
 
 use std::path::PathBuf;
 
 use assert2::assert;
-use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+use tokio_seqpacket::{SocketAddr, UnixSeqpacket, UnixSeqpacketListener};
 
 #[track_caller]
 fn random_abstract_name(suffix: &str) -> PathBuf {
@@ -39,7 +39,7 @@ async fn address_without_null_byte() {
 
 	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&name));
 	assert!(let Ok(local_addr) = listener.local_addr());
-	assert!(local_addr == name);
+	assert!(local_addr.as_abstract_name() == Some(&name.as_os_str().as_encoded_bytes()[1..]));
 
 	let (server_socket, client_socket) = tokio::join!(listener.accept(), UnixSeqpacket::connect(name),);
 	assert!(let Ok(server_socket) = server_socket);
@@ -63,7 +63,7 @@ async fn address_ending_with_null_byte() {
 
 	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&name));
 	assert!(let Ok(local_addr) = listener.local_addr());
-	assert!(local_addr == name);
+	assert!(local_addr.as_abstract_name() == Some(&name.as_os_str().as_encoded_bytes()[1..]));
 
 	let (server_socket, client_socket) = tokio::join!(listener.accept(), UnixSeqpacket::connect(name),);
 	assert!(let Ok(server_socket) = server_socket);
@@ -76,3 +76,24 @@ async fn address_ending_with_null_byte() {
 	assert!(msg_info.bytes_read() == 12);
 	assert!(&buffer[..12] == b"Hello world!");
 }
+
+/// Create a listening socket with an abstract [`SocketAddr`], connect to it and check the addresses.
+#[tokio::test]
+async fn socket_addr() {
+	let name = random_abstract_name("");
+	assert!(let Ok(address) = SocketAddr::from_abstract_name(&name.as_os_str().as_encoded_bytes()[1..]));
+	assert!(address.as_pathname() == None);
+	assert!(!address.is_unnamed());
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&address));
+	assert!(let Ok(local_addr) = listener.local_addr());
+	assert!(local_addr == address);
+
+	let (server_socket, client_socket) = tokio::join!(listener.accept(), UnixSeqpacket::connect(&address));
+	assert!(let Ok(server_socket) = server_socket);
+	assert!(let Ok(client_socket) = client_socket);
+	assert!(let Ok(local_addr) = server_socket.local_addr());
+	assert!(local_addr == address);
+	assert!(let Ok(local_addr) = client_socket.local_addr());
+	assert!(local_addr.is_unnamed());
+}
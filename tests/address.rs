@@ -0,0 +1,94 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use tempfile::tempdir;
+use tokio_seqpacket::{SocketAddr, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that we can bind and connect to a pathname [`SocketAddr`].
+#[tokio::test]
+async fn pathname() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(address) = SocketAddr::from_pathname(&path));
+	assert!(address.as_pathname() == Some(path.as_path()));
+	assert!(address.as_abstract_name() == None);
+	assert!(!address.is_unnamed());
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&address));
+	assert!(let Ok(local_addr) = listener.local_addr());
+	assert!(local_addr == address);
+
+	let (server_socket, client_socket) = tokio::join!(listener.accept(), UnixSeqpacket::connect(&address));
+	assert!(let Ok(server_socket) = server_socket);
+	assert!(let Ok(client_socket) = client_socket);
+	assert!(let Ok(local_addr) = server_socket.local_addr());
+	assert!(local_addr == address);
+	assert!(let Ok(local_addr) = client_socket.local_addr());
+	assert!(local_addr.is_unnamed());
+}
+
+/// Test that sockets created as a pair are unnamed.
+#[tokio::test]
+async fn pair_is_unnamed() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(local_addr) = a.local_addr());
+	assert!(local_addr.is_unnamed());
+	assert!(let Ok(local_addr) = b.local_addr());
+	assert!(local_addr.is_unnamed());
+}
+
+/// Test that invalid paths are rejected.
+#[test]
+fn invalid_pathname() {
+	assert!(let Err(_) = SocketAddr::from_pathname("/tmp/foo\0bar"));
+	assert!(let Err(_) = SocketAddr::from_pathname("a".repeat(200)));
+}
+
+/// Test that the client sees the address of the listener as peer address.
+#[tokio::test]
+async fn peer_addr() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+	let (server_socket, client_socket) = tokio::join!(listener.accept(), UnixSeqpacket::connect(&path));
+	assert!(let Ok(server_socket) = server_socket);
+	assert!(let Ok(client_socket) = client_socket);
+
+	assert!(let Ok(peer_addr) = client_socket.peer_addr());
+	assert!(peer_addr.as_pathname() == Some(path.as_path()));
+	assert!(let Ok(peer_addr) = server_socket.peer_addr());
+	assert!(peer_addr.is_unnamed());
+}
+
+/// Test that the server sees the automatically bound address of a client with `SO_PASSCRED` enabled.
+#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+#[tokio::test]
+async fn peer_addr_autobind() {
+	use tokio_seqpacket::UnixSeqpacketBuilder;
+
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+	let client_builder = UnixSeqpacketBuilder::new().passcred(true);
+	let (server_socket, client_socket) = tokio::join!(listener.accept(), client_builder.connect(&path));
+	assert!(let Ok(server_socket) = server_socket);
+	assert!(let Ok(client_socket) = client_socket);
+
+	assert!(let Ok(client_addr) = client_socket.local_addr());
+	assert!(let Some(_) = client_addr.as_abstract_name());
+	assert!(let Ok(peer_addr) = server_socket.peer_addr());
+	assert!(peer_addr == client_addr);
+}
+
+/// Test that both sockets of a pair have an unnamed peer address.
+#[tokio::test]
+async fn pair_peer_addr_is_unnamed() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(peer_addr) = a.peer_addr());
+	assert!(peer_addr.is_unnamed());
+	assert!(let Ok(peer_addr) = b.peer_addr());
+	assert!(peer_addr.is_unnamed());
+}
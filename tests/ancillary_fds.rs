@@ -1,3 +1,5 @@
+#![cfg(feature = "tokio")]
+
 use assert2::assert;
 use std::io::Read;
 use tokio_seqpacket::ancillary::{AncillaryMessage, OwnedAncillaryMessage};
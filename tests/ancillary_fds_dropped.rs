@@ -1,3 +1,5 @@
+#![cfg(feature = "tokio")]
+
 use assert2::assert;
 use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use tokio_seqpacket::ancillary::AncillaryMessage;
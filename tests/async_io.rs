@@ -0,0 +1,89 @@
+#![cfg(feature = "async-io")]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use tempfile::tempdir;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::async_io::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that we can connect to a listener and exchange messages on the `async-io` reactor.
+#[test]
+fn connect_and_send() {
+	async_io::block_on(async {
+		let dir = tempdir().unwrap();
+		let path = dir.path().join("listener.sock");
+		assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+		assert!(let Ok(address) = listener.local_addr());
+		assert!(address.as_pathname() == Some(path.as_path()));
+
+		let (client, server) = futures::join!(UnixSeqpacket::connect(&path), listener.accept());
+		assert!(let Ok(client) = client);
+		assert!(let Ok(server) = server);
+
+		let mut buffer = [0u8; 128];
+		let (received, sent) = futures::join!(server.recv(&mut buffer), client.send(b"Hello world!"));
+		assert!(let Ok(12) = sent);
+		assert!(let Ok(msg_info) = received);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+		assert!(let Ok(7) = server.send(b"Goodbye").await);
+		assert!(let Ok(msg_info) = client.peek(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+		assert!(let Ok(msg_info) = client.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+	})
+}
+
+/// Test that we can pass file descriptors on the `async-io` reactor.
+#[test]
+fn send_recv_with_ancillary() {
+	async_io::block_on(async {
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+		assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+
+		let mut ancillary_buffer = [0u8; 64];
+		let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+		assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+		assert!(let Ok(12) = a.send_with_ancillary(b"Hello world!", &mut ancillary).await);
+
+		let mut buffer = [0u8; 128];
+		let mut ancillary_buffer = [0u8; 64];
+		assert!(let Ok((msg_info, ancillary)) = b.recv_with_ancillary(&mut buffer, &mut ancillary_buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+		let mut messages = ancillary.into_messages();
+		assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+		assert!(let Some(fd) = fds.next());
+		assert!(let None = fds.next());
+
+		// The received file descriptor should refer to the same socket as `c`.
+		assert!(let Ok(c) = UnixSeqpacket::try_from(fd));
+		assert!(let Ok(5) = c.send(b"Hello").await);
+		assert!(let Ok(msg_info) = d.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+	})
+}
+
+/// Test that the poll functions wake up the task when the socket becomes ready.
+#[test]
+fn poll_recv_with_ancillary() {
+	async_io::block_on(async {
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+		let mut buffer = [0u8; 128];
+		let mut ancillary_buffer = [0u8; 64];
+		let receive = std::future::poll_fn(|cx| {
+			let mut buffer = [std::io::IoSliceMut::new(&mut buffer)];
+			b.poll_recv_vectored_with_ancillary(cx, &mut buffer, &mut ancillary_buffer)
+				.map(|result| result.map(|(msg_info, _ancillary)| msg_info))
+		});
+		let send = async {
+			async_io::Timer::after(std::time::Duration::from_millis(10)).await;
+			a.send(b"Hello world!").await
+		};
+		let (received, sent) = futures::join!(receive, send);
+		assert!(let Ok(12) = sent);
+		assert!(let Ok(msg_info) = received);
+		assert!(msg_info.bytes_read() == 12);
+		assert!(&buffer[..12] == b"Hello world!");
+	})
+}
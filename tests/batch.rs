@@ -0,0 +1,98 @@
+#![cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+
+use assert2::assert;
+use std::io::{Read, Seek, Write};
+use std::os::fd::AsFd;
+use tempfile::tempfile;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::{RecvBatchEntry, SendBatchEntry, UnixSeqpacket};
+
+/// Test sending and receiving multiple messages at once.
+#[tokio::test]
+async fn send_recv_batch() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let messages = [
+		SendBatchEntry::new(b"Hello"),
+		SendBatchEntry::new(b"world"),
+		SendBatchEntry::new(b"!"),
+	];
+	assert!(let Ok(3) = a.send_batch(&messages).await);
+
+	let mut buffers = [[0u8; 16]; 4];
+	let mut entries: Vec<_> = buffers.iter_mut().map(|buffer| RecvBatchEntry::new(buffer)).collect();
+	assert!(let Ok(3) = b.recv_batch(&mut entries).await);
+
+	assert!(entries[0].data() == b"Hello");
+	assert!(entries[1].data() == b"world");
+	assert!(entries[2].data() == b"!");
+	assert!(let None = entries[3].info());
+	for entry in &entries[..3] {
+		assert!(let Some(info) = entry.info());
+		assert!(!info.truncated());
+	}
+}
+
+/// Test that truncation is reported per message.
+#[tokio::test]
+async fn recv_batch_truncated() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let messages = [SendBatchEntry::new(b"Hello world!"), SendBatchEntry::new(b"Bye")];
+	assert!(let Ok(2) = a.send_batch(&messages).await);
+
+	let mut buffers = [[0u8; 5]; 2];
+	let mut entries: Vec<_> = buffers.iter_mut().map(|buffer| RecvBatchEntry::new(buffer)).collect();
+	assert!(let Ok(2) = b.recv_batch(&mut entries).await);
+
+	assert!(entries[0].data() == b"Hello");
+	assert!(entries[0].info().unwrap().truncated());
+	assert!(entries[1].data() == b"Bye");
+	assert!(!entries[1].info().unwrap().truncated());
+}
+
+/// Test that ancillary data is sent and received per message.
+#[tokio::test]
+async fn send_recv_batch_with_fds() {
+	assert!(let Ok(mut file) = tempfile());
+	assert!(let Ok(_) = file.write_all(b"Wie dit leest is gek."));
+	assert!(let Ok(()) = file.rewind());
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let mut cmsg = [0; 64];
+	let mut cmsg = AncillaryMessageWriter::new(&mut cmsg);
+	assert!(let Ok(()) = cmsg.add_fds([file.as_fd()]));
+	let messages = [
+		SendBatchEntry::new(b"No file"),
+		SendBatchEntry::with_ancillary(b"File", &cmsg),
+	];
+	assert!(let Ok(2) = a.send_batch(&messages).await);
+
+	let mut buffers = [[0u8; 16]; 2];
+	let mut ancillary_buffers = [[0u8; 64]; 2];
+	let mut entries: Vec<_> = buffers
+		.iter_mut()
+		.zip(&mut ancillary_buffers)
+		.map(|(buffer, ancillary_buffer)| RecvBatchEntry::with_ancillary(buffer, ancillary_buffer))
+		.collect();
+	assert!(let Ok(2) = b.recv_batch(&mut entries).await);
+
+	assert!(entries[0].data() == b"No file");
+	assert!(entries[0].take_ancillary().is_empty());
+
+	assert!(entries[1].data() == b"File");
+	let mut messages = entries[1].take_ancillary().into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(fd) = fds.next());
+	assert!(let None = fds.next());
+
+	let mut file = std::fs::File::from(fd);
+	let mut contents = Vec::new();
+	assert!(let Ok(_) = file.read_to_end(&mut contents));
+	assert!(contents == b"Wie dit leest is gek.");
+}
@@ -0,0 +1,151 @@
+use assert2::assert;
+use std::os::fd::AsFd;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::blocking::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that we can connect to a blocking listener and exchange messages without a tokio runtime.
+#[test]
+fn connect_and_send() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	assert!(let Ok(address) = listener.local_addr());
+	assert!(address.as_pathname() == Some(path.as_path()));
+
+	assert!(let Ok(client) = UnixSeqpacket::connect(&path));
+	assert!(let Ok(server) = listener.accept());
+
+	assert!(let Ok(12) = client.send(b"Hello world!"));
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = server.recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+	assert!(let Ok(7) = server.send(b"Goodbye"));
+	assert!(let Ok(msg_info) = client.peek(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+	assert!(let Ok(msg_info) = client.recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+}
+
+/// Test that we can pass file descriptors with blocking sockets.
+#[test]
+fn send_recv_with_ancillary() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+
+	let mut ancillary_buffer = [0u8; 64];
+	let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+	assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+	assert!(let Ok(12) = a.send_with_ancillary(b"Hello world!", &mut ancillary));
+
+	let mut buffer = [0u8; 128];
+	let mut ancillary_buffer = [0u8; 64];
+	assert!(let Ok((msg_info, ancillary)) = b.recv_with_ancillary(&mut buffer, &mut ancillary_buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+	let mut messages = ancillary.into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(fd) = fds.next());
+	assert!(let None = fds.next());
+
+	// The received file descriptor should refer to the same socket as `c`.
+	let c = UnixSeqpacket::from(fd);
+	assert!(let Ok(5) = c.send(b"Hello"));
+	assert!(let Ok(msg_info) = d.recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+}
+
+/// Test that the read timeout is applied to receive calls.
+#[test]
+fn read_timeout() {
+	assert!(let Ok((a, _b)) = UnixSeqpacket::pair());
+	assert!(let Ok(None) = a.read_timeout());
+	assert!(let Ok(None) = a.write_timeout());
+
+	// The kernel may round the timeout to its clock resolution, so use a whole second to check the getter.
+	assert!(let Ok(()) = a.set_read_timeout(Some(Duration::from_secs(1))));
+	assert!(let Ok(Some(timeout)) = a.read_timeout());
+	assert!(timeout == Duration::from_secs(1));
+
+	assert!(let Ok(()) = a.set_read_timeout(Some(Duration::from_millis(10))));
+
+	let mut buffer = [0u8; 128];
+	assert!(let Err(e) = a.recv(&mut buffer));
+	assert!(matches!(
+		e.kind(),
+		std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+	));
+
+	assert!(let Ok(()) = a.set_read_timeout(None));
+	assert!(let Ok(None) = a.read_timeout());
+	assert!(let Err(e) = a.set_read_timeout(Some(Duration::ZERO)));
+	assert!(e.kind() == std::io::ErrorKind::InvalidInput);
+}
+
+/// Test that the write timeout is applied to send calls.
+#[test]
+fn write_timeout() {
+	assert!(let Ok((a, _b)) = UnixSeqpacket::pair());
+	assert!(let Ok(()) = a.set_write_timeout(Some(Duration::from_secs(1))));
+	assert!(let Ok(Some(timeout)) = a.write_timeout());
+	assert!(timeout == Duration::from_secs(1));
+
+	assert!(let Ok(()) = a.set_write_timeout(Some(Duration::from_millis(10))));
+
+	// Fill the send buffer until a send call times out.
+	let result = loop {
+		if let Err(e) = a.send(&[0; 1024]) {
+			break e;
+		}
+	};
+	assert!(matches!(
+		result.kind(),
+		std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+	));
+}
+
+/// Test that we can convert between blocking and async sockets.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn convert_socket() {
+	assert!(let Ok((a, b)) = tokio_seqpacket::UnixSeqpacket::pair());
+	assert!(let Ok(a) = UnixSeqpacket::try_from(a));
+	assert!(let Ok(12) = a.send(b"Hello world!"));
+
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+	// The blocking socket should really be blocking again.
+	assert!(let Ok(()) = a.set_read_timeout(Some(Duration::from_millis(10))));
+	assert!(let Err(e) = a.recv(&mut buffer));
+	assert!(matches!(
+		e.kind(),
+		std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+	));
+
+	assert!(let Ok(a) = tokio_seqpacket::UnixSeqpacket::try_from(a));
+	let (received, sent) = tokio::join!(a.recv(&mut buffer), b.send(b"Goodbye"));
+	assert!(let Ok(7) = sent);
+	assert!(let Ok(msg_info) = received);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+}
+
+/// Test that we can convert between blocking and async listeners.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn convert_listener() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+
+	assert!(let Ok(mut listener) = tokio_seqpacket::UnixSeqpacketListener::try_from(listener));
+	let (server, client) = tokio::join!(listener.accept(), tokio_seqpacket::UnixSeqpacket::connect(&path));
+	assert!(let Ok(_server) = server);
+	assert!(let Ok(_client) = client);
+
+	assert!(let Ok(listener) = UnixSeqpacketListener::try_from(listener));
+	assert!(let Ok(_client) = tokio_seqpacket::UnixSeqpacket::connect(&path).await);
+	assert!(let Ok(_server) = listener.accept());
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::tempdir;
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketBuilder, UnixSeqpacketListener};
+
+/// Test that options are applied to a listener and connected sockets.
+#[tokio::test]
+async fn bind_and_connect() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	let builder = UnixSeqpacketBuilder::new().recv_buffer_size(48 * 1024);
+	assert!(let Ok(mut listener) = builder.bind(&path));
+	assert!(let Ok(size) = listener.recv_buffer_size());
+	assert!(size >= 48 * 1024);
+
+	let (server, client) = tokio::join!(listener.accept(), builder.connect(&path));
+	assert!(let Ok(server) = server);
+	assert!(let Ok(client) = client);
+	assert!(let Ok(size) = client.recv_buffer_size());
+	assert!(size >= 48 * 1024);
+
+	assert!(let Ok(12) = client.send(b"Hello world!").await);
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = server.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+}
+
+/// Test that the file mode is applied to the socket file.
+#[tokio::test]
+async fn bind_with_mode() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(_listener) = UnixSeqpacketBuilder::new().mode(0o600).bind(&path));
+	assert!(let Ok(metadata) = std::fs::metadata(&path));
+	assert!(metadata.permissions().mode() & 0o777 == 0o600);
+}
+
+/// Test that options are applied to both sockets of a pair.
+#[tokio::test]
+async fn pair() {
+	assert!(let Ok((a, b)) = UnixSeqpacketBuilder::new().send_buffer_size(32 * 1024).pair());
+	assert!(let Ok(size) = a.send_buffer_size());
+	assert!(size >= 32 * 1024);
+	assert!(let Ok(size) = b.send_buffer_size());
+	assert!(size >= 32 * 1024);
+}
+
+/// Test that a stale socket file is replaced and removed again when the listener is dropped.
+#[tokio::test]
+async fn cleanup_stale_socket() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	// Leave behind a socket file that nobody is listening on.
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	drop(listener);
+	assert!(path.exists());
+	assert!(let Err(_) = UnixSeqpacketListener::bind(&path));
+
+	let builder = UnixSeqpacketBuilder::new().cleanup(true);
+	assert!(let Ok(mut listener) = builder.bind(&path));
+	let (server, client) = tokio::join!(listener.accept(), UnixSeqpacket::connect(&path));
+	assert!(let Ok(_server) = server);
+	assert!(let Ok(_client) = client);
+
+	drop(listener);
+	assert!(!path.exists());
+}
+
+/// Test that a socket file is not replaced while someone is listening on it.
+#[tokio::test]
+async fn cleanup_does_not_replace_live_socket() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(_listener) = UnixSeqpacketListener::bind(&path));
+	assert!(let Err(e) = UnixSeqpacketBuilder::new().cleanup(true).bind(&path));
+	assert!(e.kind() == std::io::ErrorKind::AddrInUse);
+	assert!(let Ok(_client) = UnixSeqpacket::connect(&path).await);
+}
+
+/// Test that a file that is not a socket is not replaced.
+#[tokio::test]
+async fn cleanup_does_not_replace_regular_file() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	std::fs::write(&path, b"not a socket").unwrap();
+	assert!(let Err(e) = UnixSeqpacketBuilder::new().cleanup(true).bind(&path));
+	assert!(e.kind() == std::io::ErrorKind::AddrInUse);
+	assert!(let Ok(contents) = std::fs::read(&path));
+	assert!(contents == b"not a socket");
+}
+
+/// Test that dropping the listener does not remove a socket file that was replaced by someone else.
+#[tokio::test]
+async fn cleanup_leaves_replaced_socket() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(old) = UnixSeqpacketBuilder::new().cleanup(true).bind(&path));
+	std::fs::remove_file(&path).unwrap();
+	assert!(let Ok(_new) = UnixSeqpacketListener::bind(&path));
+
+	drop(old);
+	assert!(path.exists());
+	assert!(let Ok(_client) = UnixSeqpacket::connect(&path).await);
+}
+
+/// Test that a stale socket file is replaced when binding with a file mode.
+#[tokio::test]
+async fn cleanup_stale_socket_with_mode() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	drop(listener);
+
+	assert!(let Ok(listener) = UnixSeqpacketBuilder::new().cleanup(true).mode(0o600).bind(&path));
+	assert!(let Ok(_client) = UnixSeqpacket::connect(&path).await);
+	drop(listener);
+	assert!(!path.exists());
+}
+
+/// Test that the builder can create blocking listeners and sockets.
+#[test]
+fn blocking() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	let builder = UnixSeqpacketBuilder::new().mode(0o600).cleanup(true);
+	assert!(let Ok(listener) = builder.blocking_bind(&path));
+	assert!(let Ok(address) = listener.local_addr());
+	assert!(address.as_pathname() == Some(path.as_path()));
+	assert!(let Ok(metadata) = std::fs::metadata(&path));
+	assert!(metadata.permissions().mode() & 0o777 == 0o600);
+
+	assert!(let Ok(client) = builder.blocking_connect(&path));
+	assert!(let Ok(server) = listener.accept());
+	assert!(let Ok(12) = client.send(b"Hello world!"));
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = server.recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+	drop(listener);
+	assert!(!path.exists());
+
+	assert!(let Ok((a, b)) = builder.blocking_pair());
+	assert!(let Ok(3) = a.send(b"Bye"));
+	assert!(let Ok(msg_info) = b.recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Bye");
+}
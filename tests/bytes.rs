@@ -0,0 +1,85 @@
+#![cfg(feature = "bytes")]
+
+use assert2::assert;
+use bytes::{Buf, BufMut, BytesMut};
+use std::os::fd::AsFd;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::UnixSeqpacket;
+
+/// Test that a chained buffer is sent as a single message.
+#[tokio::test]
+async fn send_chained_buf() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let buffer = (&b"Hello"[..]).chain(&b" "[..]).chain(&b"world!"[..]);
+	assert!(let Ok(12) = a.send_buf(buffer).await);
+
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+}
+
+/// Test that a message is received into the spare capacity of a `BytesMut`.
+#[tokio::test]
+async fn recv_into_bytes_mut() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let mut buffer = BytesMut::with_capacity(128);
+	buffer.put_slice(b"prefix:");
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+	assert!(let Ok(msg_info) = b.recv_buf(&mut buffer).await);
+	assert!(msg_info.bytes_read() == 12);
+	assert!(!msg_info.truncated());
+	assert!(&buffer[..] == b"prefix:Hello world!");
+
+	// A message that does not fit in the spare capacity is truncated.
+	let mut buffer = BytesMut::with_capacity(4);
+	assert!(let Ok(7) = a.send(b"Goodbye").await);
+	let mut limited = (&mut buffer).limit(4);
+	assert!(let Ok(msg_info) = b.recv_buf(&mut limited).await);
+	assert!(msg_info.truncated());
+	assert!(&buffer[..] == b"Good");
+}
+
+/// Test that a `BytesMut` without spare capacity only reserves 64 bytes for the message.
+#[tokio::test]
+async fn recv_into_empty_bytes_mut() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let mut buffer = BytesMut::new();
+	assert!(let Ok(100) = a.send(&[1; 100]).await);
+	assert!(let Ok(msg_info) = b.recv_buf(&mut buffer).await);
+	assert!(msg_info.truncated());
+	assert!(buffer.len() == 64);
+	assert!(buffer.iter().all(|&byte| byte == 1));
+}
+
+/// Test that we can send and receive file descriptors with `Buf` and `BufMut`.
+#[tokio::test]
+async fn send_recv_buf_with_ancillary() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+
+	let mut ancillary_buffer = [0u8; 64];
+	let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+	assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+	let buffer = (&b"Hello "[..]).chain(&b"world!"[..]);
+	assert!(let Ok(12) = a.send_buf_with_ancillary(buffer, &mut ancillary).await);
+
+	let mut buffer = BytesMut::with_capacity(128);
+	let mut ancillary_buffer = [0u8; 64];
+	assert!(let Ok((msg_info, ancillary)) = b.recv_buf_with_ancillary(&mut buffer, &mut ancillary_buffer).await);
+	assert!(msg_info.bytes_read() == 12);
+	assert!(&buffer[..] == b"Hello world!");
+	let mut messages = ancillary.into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(fd) = fds.next());
+	assert!(let None = fds.next());
+
+	// The received file descriptor should refer to the same socket as `c`.
+	assert!(let Ok(c) = UnixSeqpacket::try_from(fd));
+	assert!(let Ok(5) = c.send(b"Hello").await);
+	let mut buffer = [0u8; 16];
+	assert!(let Ok(msg_info) = d.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "codec")]
+
+use assert2::assert;
+use futures::{SinkExt, StreamExt};
+use std::io::{Read, Seek, Write};
+use tempfile::tempfile;
+use tokio_seqpacket::{SeqpacketFramed, UnixSeqpacket};
+use tokio_util::codec::{LinesCodec, LinesCodecError};
+
+/// Test that each message is decoded as exactly one frame.
+#[tokio::test]
+async fn one_frame_per_message() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut a = SeqpacketFramed::new(a, LinesCodec::new());
+	let mut b = SeqpacketFramed::new(b, LinesCodec::new());
+
+	assert!(let Ok(()) = a.send(("Hello world!", Vec::new())).await);
+	assert!(let Ok(()) = a.send(("Bye", Vec::new())).await);
+
+	assert!(let Some(Ok((line, fds))) = b.next().await);
+	assert!(line == "Hello world!");
+	assert!(fds.is_empty());
+	assert!(let Some(Ok((line, _fds))) = b.next().await);
+	assert!(line == "Bye");
+}
+
+/// Test that file descriptors are passed along with the frame.
+#[tokio::test]
+async fn pass_fd() {
+	assert!(let Ok(mut file) = tempfile());
+	assert!(let Ok(_) = file.write_all(b"Wie dit leest is gek."));
+	assert!(let Ok(()) = file.rewind());
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut a = SeqpacketFramed::new(a, LinesCodec::new());
+	let mut b = SeqpacketFramed::new(b, LinesCodec::new());
+
+	assert!(let Ok(()) = a.send(("Here, have a file descriptor.", vec![file.into()])).await);
+
+	assert!(let Some(Ok((line, fds))) = b.next().await);
+	assert!(line == "Here, have a file descriptor.");
+	assert!(fds.len() == 1);
+
+	let mut file = std::fs::File::from(fds.into_iter().next().unwrap());
+	let mut contents = Vec::new();
+	assert!(let Ok(_) = file.read_to_end(&mut contents));
+	assert!(contents == b"Wie dit leest is gek.");
+}
+
+/// Test that data after the frame in a message is reported as an error, not as the next frame.
+#[tokio::test]
+async fn trailing_data() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut b = SeqpacketFramed::new(b, LinesCodec::new());
+
+	assert!(let Ok(_) = a.send(b"Hello\nworld!").await);
+	assert!(let Ok(_) = a.send(b"Bye").await);
+
+	assert!(let Some(Err(LinesCodecError::Io(e))) = b.next().await);
+	assert!(e.kind() == std::io::ErrorKind::InvalidData);
+	assert!(e.to_string() == "message contains trailing data after the frame");
+	assert!(let Some(Ok((line, _fds))) = b.next().await);
+	assert!(line == "Bye");
+}
+
+/// Test that a message larger than the receive capacity is reported as an error.
+#[tokio::test]
+async fn message_too_large() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut b = SeqpacketFramed::with_capacity(b, LinesCodec::new(), 8, 64);
+
+	assert!(let Ok(_) = a.send(b"Hello world!").await);
+	assert!(let Ok(_) = a.send(b"Bye").await);
+
+	assert!(let Some(Err(LinesCodecError::Io(e))) = b.next().await);
+	assert!(e.kind() == std::io::ErrorKind::InvalidData);
+	assert!(let Some(Ok((line, _fds))) = b.next().await);
+	assert!(line == "Bye");
+}
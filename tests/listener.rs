@@ -1,3 +1,5 @@
+#![cfg(feature = "tokio")]
+
 use assert2::assert;
 use tempfile::tempdir;
 use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
@@ -11,7 +13,7 @@ async fn unix_seqpacket_listener() {
 	let server_task = tokio::spawn({
 		assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
 		assert!(let Ok(local_address) = listener.local_addr());
-		assert!(local_address == path);
+		assert!(local_address.as_pathname() == Some(path.as_path()));
 		async move {
 			for _ in 0..2 {
 				assert!(let Ok(peer) = listener.accept().await);
@@ -43,7 +45,7 @@ async fn connect_blocking() {
 	let server_task = tokio::spawn({
 		assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
 		assert!(let Ok(local_address) = listener.local_addr());
-		assert!(local_address == path);
+		assert!(local_address.as_pathname() == Some(path.as_path()));
 		async move {
 			for _ in 0..2 {
 				assert!(let Ok(peer) = listener.accept().await);
@@ -73,3 +75,101 @@ async fn connect_blocking() {
 
 	assert!(let Ok(()) = server_task.await);
 }
+
+/// Test that we can accept connections through the incoming stream.
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn incoming_stream() {
+	use futures::StreamExt;
+
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	let server_task = tokio::spawn(async move {
+		let mut incoming = listener.into_stream().with_credentials();
+		for _ in 0..2 {
+			assert!(let Some(Ok((peer, cred))) = incoming.next().await);
+			assert!(cred.uid() == unsafe { libc::getuid() });
+			assert!(let Ok(_) = peer.send(b"Hello!").await);
+		}
+	});
+
+	for _ in 0..2 {
+		assert!(let Ok(peer) = UnixSeqpacket::connect(&path).await);
+		let mut buf = [0u8; 128];
+		assert!(let Ok(msg_info) = peer.recv(&mut buf).await);
+		assert!(&buf[..msg_info.bytes_read()] == b"Hello!");
+	}
+
+	assert!(let Ok(()) = server_task.await);
+}
+
+/// Test that the incoming stream can borrow the listener.
+#[cfg(feature = "futures")]
+#[tokio::test]
+async fn incoming_stream_borrowed() {
+	use futures::StreamExt;
+
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+	let mut incoming = listener.incoming();
+	let (server_socket, client_socket) = tokio::join!(incoming.next(), UnixSeqpacket::connect(&path));
+	assert!(let Some(Ok(server_socket)) = server_socket);
+	assert!(let Ok(client_socket) = client_socket);
+
+	assert!(let Ok(12) = client_socket.send(b"Hello world!").await);
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = server_socket.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+	// The listener should still be usable after we're done with the stream.
+	let listener = incoming.into_inner();
+	assert!(let Ok(_) = listener.local_addr());
+}
+
+/// Test that the permissions are applied before the socket file appears at the requested path.
+#[tokio::test]
+async fn bind_with_permissions() {
+	use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	let uid = unsafe { libc::geteuid() };
+	let gid = unsafe { libc::getegid() };
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind_with_permissions(&path, 0o640, Some(uid), Some(gid)));
+	assert!(let Ok(metadata) = std::fs::metadata(&path));
+	assert!(metadata.permissions().mode() & 0o777 == 0o640);
+	assert!(metadata.uid() == uid);
+	assert!(metadata.gid() == gid);
+
+	assert!(let Ok(local_address) = listener.local_addr());
+	assert!(local_address.as_pathname() == Some(path.as_path()));
+
+	// The temporary directory should be gone.
+	assert!(let Ok(entries) = std::fs::read_dir(dir.path()));
+	assert!(entries.count() == 1);
+
+	let (server, client) = tokio::join!(listener.accept(), UnixSeqpacket::connect(&path));
+	assert!(let Ok(_server) = server);
+	assert!(let Ok(_client) = client);
+}
+
+/// Test that binding with permissions does not replace an existing file.
+#[tokio::test]
+async fn bind_with_permissions_existing_path() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	std::fs::write(&path, b"not a socket").unwrap();
+	assert!(let Err(e) = UnixSeqpacketListener::bind_with_permissions(&path, 0o600, None, None));
+	assert!(e.kind() == std::io::ErrorKind::AddrInUse);
+	assert!(let Ok(contents) = std::fs::read(&path));
+	assert!(contents == b"not a socket");
+
+	assert!(let Ok(entries) = std::fs::read_dir(dir.path()));
+	assert!(entries.count() == 1);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "tokio")]
+
 use assert2::assert;
 use std::io::{IoSlice, IoSliceMut, Read, Seek, Write};
 use tempfile::tempfile;
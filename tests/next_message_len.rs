@@ -0,0 +1,50 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::UnixSeqpacket;
+
+/// Test that the size of the next message is reported without consuming it.
+#[tokio::test]
+async fn next_message_len() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let message: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+	assert!(let Ok(5000) = a.send(&message).await);
+	assert!(let Ok(2) = a.send(b"Hi").await);
+
+	assert!(let Ok(5000) = b.next_message_len().await);
+	assert!(let Ok(5000) = b.next_message_len().await);
+	let mut buffer = vec![0; 5000];
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(!msg_info.truncated());
+	assert!(buffer == message);
+
+	assert!(let Ok(2) = std::future::poll_fn(|cx| b.poll_next_message_len(cx)).await);
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hi");
+}
+
+/// Test that getting the size of the next message leaves the ancillary data untouched.
+#[tokio::test]
+async fn next_message_len_keeps_ancillary() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok((c, _d)) = UnixSeqpacket::pair());
+
+	let mut ancillary_buffer = [0u8; 64];
+	let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+	assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+	assert!(let Ok(12) = a.send_with_ancillary(b"Hello world!", &mut ancillary).await);
+
+	assert!(let Ok(12) = b.next_message_len().await);
+
+	let mut buffer = [0u8; 12];
+	let mut ancillary_buffer = [0u8; 64];
+	assert!(let Ok((msg_info, ancillary)) = b.recv_with_ancillary(&mut buffer, &mut ancillary_buffer).await);
+	assert!(!msg_info.ancillary_truncated());
+	assert!(&buffer == b"Hello world!");
+	let mut messages = ancillary.into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(_fd) = fds.next());
+	assert!(let None = fds.next());
+}
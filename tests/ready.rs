@@ -0,0 +1,63 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::time::Duration;
+use tokio::io::Interest;
+use tokio_seqpacket::UnixSeqpacket;
+
+/// Test that we can wait for readiness without doing I/O.
+#[tokio::test]
+async fn readable_writable() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	assert!(let Ok(()) = a.writable().await);
+	assert!(let Err(_) = tokio::time::timeout(Duration::from_millis(10), b.readable()).await);
+
+	assert!(let Ok(12) = a.try_send(b"Hello world!"));
+	assert!(let Ok(Ok(())) = tokio::time::timeout(Duration::from_secs(1), b.readable()).await);
+
+	// Waiting for readiness must not consume the message.
+	assert!(let Ok(ready) = b.ready(Interest::READABLE).await);
+	assert!(ready.is_readable());
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = b.try_recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+}
+
+/// Test that a peer hangup is reported as read-closed readiness.
+#[tokio::test]
+async fn read_closed() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	assert!(let Ok(()) = a.shutdown(std::net::Shutdown::Write));
+	assert!(let Ok(Ok(ready)) = tokio::time::timeout(Duration::from_secs(1), b.ready(Interest::READABLE)).await);
+	assert!(ready.is_read_closed());
+	assert!(!ready.is_write_closed());
+
+	drop(a);
+	let ready = tokio::time::timeout(Duration::from_secs(1), async {
+		loop {
+			let ready = b.ready(Interest::READABLE | Interest::WRITABLE).await?;
+			if ready.is_write_closed() {
+				return Ok::<_, std::io::Error>(ready);
+			}
+			tokio::task::yield_now().await;
+		}
+	});
+	assert!(let Ok(Ok(ready)) = ready.await);
+	assert!(ready.is_read_closed());
+	assert!(ready.is_write_closed());
+}
+
+/// Test that we can poll for readiness.
+#[tokio::test]
+async fn poll_ready() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	assert!(let Ok(()) = std::future::poll_fn(|cx| a.poll_send_ready(cx)).await);
+	assert!(let Ok(12) = a.try_send(b"Hello world!"));
+	assert!(let Ok(()) = std::future::poll_fn(|cx| b.poll_recv_ready(cx)).await);
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(msg_info) = b.try_recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+}
@@ -0,0 +1,60 @@
+#![cfg(all(
+	feature = "tokio",
+	feature = "non-portable",
+	any(target_os = "linux", target_os = "android")
+))]
+
+use assert2::assert;
+use std::io::{IoSlice, Read, Seek, Write};
+use std::os::fd::AsFd;
+use tempfile::tempfile;
+use tokio_seqpacket::ancillary::AncillaryMessageWriter;
+use tokio_seqpacket::UnixSeqpacket;
+
+/// Test that we receive the full message without knowing the size up front.
+#[tokio::test]
+async fn recv_large_message() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let payload: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+	assert!(let Ok(10_000) = a.send(&payload).await);
+
+	assert!(let Ok(message) = b.recv_message().await);
+	assert!(message.data == payload);
+	assert!(message.fds.is_empty());
+}
+
+/// Test that we receive many file descriptors without knowing the number up front.
+#[tokio::test]
+async fn recv_many_fds() {
+	assert!(let Ok(mut file) = tempfile());
+	assert!(let Ok(_) = file.write_all(b"Wie dit leest is gek."));
+	assert!(let Ok(()) = file.rewind());
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut cmsg = [0; 512];
+	let mut cmsg = AncillaryMessageWriter::new(&mut cmsg);
+	assert!(let Ok(()) = cmsg.add_fds([file.as_fd(); 32]));
+	assert!(let Ok(5) = a.send_vectored_with_ancillary(&[IoSlice::new(b"Hello")], &mut cmsg).await);
+
+	assert!(let Ok(message) = b.recv_message().await);
+	assert!(message.data == b"Hello");
+	assert!(message.fds.len() == 32);
+
+	let mut file = std::fs::File::from(message.fds.into_iter().next().unwrap());
+	let mut contents = Vec::new();
+	assert!(let Ok(_) = file.read_to_end(&mut contents));
+	assert!(contents == b"Wie dit leest is gek.");
+}
+
+/// Test that message boundaries are preserved.
+#[tokio::test]
+async fn recv_message_boundaries() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+	assert!(let Ok(3) = a.send(b"Bye").await);
+
+	assert!(let Ok(message) = b.recv_message().await);
+	assert!(message.data == b"Hello world!");
+	assert!(let Ok(message) = b.recv_message().await);
+	assert!(message.data == b"Bye");
+}
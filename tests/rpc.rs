@@ -0,0 +1,185 @@
+#![cfg(feature = "rpc")]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use std::time::Duration;
+use tokio_seqpacket::rpc::{self, RpcClient, RpcError};
+use tokio_seqpacket::{OutgoingMessage, UnixSeqpacket};
+
+/// Test that concurrent calls from a shared client each get their own reply.
+#[tokio::test]
+async fn concurrent_calls() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(rpc::serve(server, |request| async move {
+		// Reply in a different order than the requests arrived.
+		let delay = 10 - request.data[0] as u64;
+		tokio::time::sleep(Duration::from_millis(delay)).await;
+		Ok(OutgoingMessage::new(request.data))
+	}));
+
+	let client = RpcClient::new(client);
+	let calls = (0..10u8).map(|i| client.call(OutgoingMessage::new(vec![i])));
+	let replies = futures::future::join_all(calls).await;
+	for (i, reply) in replies.into_iter().enumerate() {
+		assert!(let Ok(reply) = reply);
+		assert!(reply.data == [i as u8]);
+	}
+}
+
+/// Test that requests and replies can carry file descriptors.
+#[tokio::test]
+async fn pass_fds() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(rpc::serve(server, |mut request| async move {
+		// Send the received file descriptor back.
+		Ok(OutgoingMessage {
+			data: b"fd".to_vec(),
+			fds: std::mem::take(&mut request.fds),
+		})
+	}));
+
+	let client = RpcClient::new(client);
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(a) = a.as_fd().try_clone_to_owned());
+	let request = OutgoingMessage {
+		data: b"fd".to_vec(),
+		fds: vec![a],
+	};
+	assert!(let Ok(mut reply) = client.call(request).await);
+	assert!(reply.fds.len() == 1);
+
+	// The returned file descriptor should refer to the same socket as `a`.
+	assert!(let Ok(a) = UnixSeqpacket::try_from(reply.fds.remove(0)));
+	assert!(let Ok(5) = a.send(b"Hello").await);
+	let mut buffer = [0u8; 16];
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+}
+
+/// Test that an error from the handler is returned to the caller.
+#[tokio::test]
+async fn remote_error() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(rpc::serve(server, |_request| async move {
+		Err(std::io::Error::other("no such thing"))
+	}));
+
+	let client = RpcClient::new(client);
+	assert!(let Err(RpcError::Remote(message)) = client.call(OutgoingMessage::new("get")).await);
+	assert!(message == "no such thing");
+}
+
+/// Test that a panicking handler results in an error instead of a call that never completes.
+#[tokio::test]
+async fn handler_panic() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(rpc::serve(server, |_request| async move {
+		panic!("oops");
+	}));
+
+	let client = RpcClient::new(client);
+	assert!(let Err(RpcError::Remote(_)) = client.call(OutgoingMessage::new("get")).await);
+}
+
+/// Test that a call that times out sends a cancellation notice that aborts the handler.
+#[tokio::test]
+async fn timeout_cancels_handler() {
+	struct DropNotify(Option<tokio::sync::oneshot::Sender<()>>);
+	impl Drop for DropNotify {
+		fn drop(&mut self) {
+			if let Some(notify) = self.0.take() {
+				notify.send(()).ok();
+			}
+		}
+	}
+
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel();
+	let dropped_tx = std::sync::Mutex::new(Some(dropped_tx));
+	tokio::spawn(rpc::serve(server, move |request| {
+		let notify = DropNotify(dropped_tx.lock().unwrap().take());
+		async move {
+			let _notify = notify;
+			if request.data == b"slow" {
+				std::future::pending::<()>().await;
+			}
+			Ok(OutgoingMessage::new(request.data))
+		}
+	}));
+
+	let client = RpcClient::new(client);
+	let result = client
+		.call_timeout(OutgoingMessage::new("slow"), Duration::from_millis(10))
+		.await;
+	assert!(let Err(RpcError::TimedOut) = result);
+	assert!(let Ok(Ok(())) = tokio::time::timeout(Duration::from_secs(1), dropped_rx).await);
+
+	// The client can still be used after a call timed out.
+	assert!(let Ok(reply) = client.call(OutgoingMessage::new("fast")).await);
+	assert!(reply.data == b"fast");
+}
+
+/// Test that outstanding and new calls fail when the connection is closed.
+#[tokio::test]
+async fn disconnected() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	let client = RpcClient::new(client);
+
+	let call = client.call(OutgoingMessage::new("hello"));
+	let close = async {
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		drop(server);
+	};
+	let (result, ()) = tokio::join!(call, close);
+	assert!(let Err(RpcError::Disconnected) = result);
+	assert!(let Err(RpcError::Disconnected) = client.call(OutgoingMessage::new("hello")).await);
+}
+
+/// Test that the server ignores invalid messages and keeps serving requests.
+#[tokio::test]
+async fn server_skips_invalid_messages() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(rpc::serve(server, |request| async move {
+		Ok(OutgoingMessage::new(request.data))
+	}));
+
+	// A message shorter than the header, and a message of an unknown kind.
+	assert!(let Ok(_) = client.send(b"bad").await);
+	assert!(let Ok(_) = client.send(&[99; 9]).await);
+
+	let mut request = vec![1];
+	request.extend_from_slice(&5u64.to_le_bytes());
+	request.extend_from_slice(b"hello");
+	assert!(let Ok(_) = client.send(&request).await);
+
+	let mut buffer = [0u8; 64];
+	assert!(let Ok(msg_info) = client.recv(&mut buffer).await);
+	let reply = &buffer[..msg_info.bytes_read()];
+	assert!(reply[0] == 2);
+	assert!(reply[1..9] == 5u64.to_le_bytes());
+	assert!(&reply[9..] == b"hello");
+}
+
+/// Test that the client ignores invalid messages and still receives the reply.
+#[tokio::test]
+async fn client_skips_invalid_messages() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	let client = RpcClient::new(client);
+
+	let respond = async {
+		let mut buffer = [0u8; 64];
+		assert!(let Ok(msg_info) = server.recv(&mut buffer).await);
+		assert!(msg_info.bytes_read() >= 9);
+		assert!(buffer[0] == 1);
+
+		assert!(let Ok(_) = server.send(b"bad").await);
+		assert!(let Ok(_) = server.send(&[99; 9]).await);
+		let mut reply = vec![2];
+		reply.extend_from_slice(&buffer[1..9]);
+		reply.extend_from_slice(b"world");
+		assert!(let Ok(_) = server.send(&reply).await);
+	};
+	let (result, ()) = tokio::join!(client.call(OutgoingMessage::new("hello")), respond);
+	assert!(let Ok(reply) = result);
+	assert!(reply.data == b"world");
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "server")]
+
+use assert2::assert;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio_seqpacket::{ConnectionError, Server, UnixSeqpacket, UnixSeqpacketListener};
+
+/// Collect connection errors reported by a server.
+fn collect_errors() -> (
+	Arc<Mutex<Vec<ConnectionError>>>,
+	impl Fn(ConnectionError) + Send + Sync + 'static,
+) {
+	let errors = Arc::new(Mutex::new(Vec::new()));
+	let callback = {
+		let errors = errors.clone();
+		move |error| errors.lock().unwrap().push(error)
+	};
+	(errors, callback)
+}
+
+/// Test that the server runs the handler for each connection and stops on shutdown.
+#[tokio::test]
+async fn echo() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("server.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+
+	let (errors, on_error) = collect_errors();
+	let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+	let server = Server::new(listener, |socket, credentials| async move {
+		assert!(credentials.pid() == Some(std::process::id() as libc::pid_t));
+		let mut buffer = [0; 128];
+		let msg_info = socket.recv(&mut buffer).await?;
+		socket.send(&buffer[..msg_info.bytes_read()]).await?;
+		Ok(())
+	})
+	.on_error(on_error);
+	let server = tokio::spawn(server.run(async {
+		shutdown_rx.await.ok();
+	}));
+
+	for _ in 0..3 {
+		assert!(let Ok(client) = UnixSeqpacket::connect(&path).await);
+		assert!(let Ok(12) = client.send(b"Hello world!").await);
+		let mut buffer = [0; 128];
+		assert!(let Ok(msg_info) = client.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+	}
+
+	assert!(let Ok(()) = shutdown_tx.send(()));
+	assert!(let Ok(()) = server.await);
+	assert!(errors.lock().unwrap().is_empty());
+
+	// The listener should be closed after shutdown.
+	assert!(let Err(_) = UnixSeqpacket::connect(&path).await);
+}
+
+/// Test that the server does not run more handlers than allowed at the same time.
+#[tokio::test]
+async fn max_connections() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("server.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+
+	let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+	let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+	let server = Server::new(listener, move |socket, _credentials| {
+		let started_tx = started_tx.clone();
+		async move {
+			started_tx.send(()).ok();
+			// Wait for the client to close the connection.
+			let mut buffer = [0; 128];
+			while socket.recv(&mut buffer).await?.bytes_read() != 0 {}
+			Ok(())
+		}
+	})
+	.max_connections(1);
+	let server = tokio::spawn(server.run(async {
+		shutdown_rx.await.ok();
+	}));
+
+	assert!(let Ok(first) = UnixSeqpacket::connect(&path).await);
+	assert!(let Some(()) = started_rx.recv().await);
+
+	// The second connection should wait until the first one is closed.
+	assert!(let Ok(second) = UnixSeqpacket::connect(&path).await);
+	assert!(let Err(_) = tokio::time::timeout(Duration::from_millis(50), started_rx.recv()).await);
+	drop(first);
+	assert!(let Ok(Some(())) = tokio::time::timeout(Duration::from_secs(1), started_rx.recv()).await);
+
+	assert!(let Ok(()) = shutdown_tx.send(()));
+	drop(second);
+	assert!(let Ok(()) = server.await);
+}
+
+/// Test that a connection limit of zero is rejected.
+#[tokio::test]
+#[should_panic(expected = "max_connections must be at least 1")]
+async fn max_connections_zero() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("server.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	let _server = Server::new(listener, |_socket, _credentials| async { Ok(()) }).max_connections(0);
+}
+
+/// Test that connections that do not send anything are dropped after the handshake timeout.
+#[tokio::test]
+async fn handshake_timeout() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("server.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+
+	let (errors, on_error) = collect_errors();
+	let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+	let server = Server::new(listener, |_socket, _credentials| async move { Ok(()) })
+		.handshake_timeout(Duration::from_millis(20))
+		.on_error(on_error);
+	let server = tokio::spawn(server.run(async {
+		shutdown_rx.await.ok();
+	}));
+
+	assert!(let Ok(client) = UnixSeqpacket::connect(&path).await);
+	let mut buffer = [0; 128];
+	assert!(let Ok(Ok(msg_info)) = tokio::time::timeout(Duration::from_secs(1), client.recv(&mut buffer)).await);
+	assert!(msg_info.bytes_read() == 0);
+
+	assert!(let Ok(()) = shutdown_tx.send(()));
+	assert!(let Ok(()) = server.await);
+	let errors = errors.lock().unwrap();
+	assert!(errors.len() == 1);
+	assert!(let ConnectionError::HandshakeTimeout(_) = &errors[0]);
+}
+
+/// Test that handler errors are reported and that handlers are aborted after the grace period.
+#[tokio::test]
+async fn handler_errors_and_grace_period() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("server.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+
+	let (errors, on_error) = collect_errors();
+	let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+	let server = Server::new(listener, |socket, _credentials| async move {
+		let mut buffer = [0; 128];
+		let msg_info = socket.recv(&mut buffer).await?;
+		match &buffer[..msg_info.bytes_read()] {
+			b"fail" => Err(std::io::Error::other("requested failure")),
+			_ => std::future::pending().await,
+		}
+	})
+	.grace_period(Duration::from_millis(20))
+	.on_error(on_error);
+	let server = tokio::spawn(server.run(async {
+		shutdown_rx.await.ok();
+	}));
+
+	assert!(let Ok(failing) = UnixSeqpacket::connect(&path).await);
+	assert!(let Ok(_) = failing.send(b"fail").await);
+	let mut buffer = [0; 128];
+	assert!(let Ok(msg_info) = failing.recv(&mut buffer).await);
+	assert!(msg_info.bytes_read() == 0);
+
+	assert!(let Ok(hanging) = UnixSeqpacket::connect(&path).await);
+	assert!(let Ok(_) = hanging.send(b"hang").await);
+	tokio::time::sleep(Duration::from_millis(10)).await;
+
+	assert!(let Ok(()) = shutdown_tx.send(()));
+	assert!(let Ok(Ok(())) = tokio::time::timeout(Duration::from_secs(1), server).await);
+	assert!(let Ok(msg_info) = hanging.recv(&mut buffer).await);
+	assert!(msg_info.bytes_read() == 0);
+
+	let errors = errors.lock().unwrap();
+	assert!(errors.len() == 2);
+	assert!(let ConnectionError::Handler(_, e) = &errors[0]);
+	assert!(e.to_string() == "requested failure");
+	assert!(let ConnectionError::Aborted = &errors[1]);
+}
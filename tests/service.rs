@@ -0,0 +1,116 @@
+#![cfg(feature = "tower")]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio_seqpacket::rpc::RpcError;
+use tokio_seqpacket::service::{serve, serve_connection};
+use tokio_seqpacket::{OutgoingMessage, ReceivedMessage, SeqpacketClient, UnixSeqpacket, UnixSeqpacketListener};
+use tower::{service_fn, Service, ServiceBuilder, ServiceExt};
+
+/// Echo the data and file descriptors of a request.
+async fn echo(mut request: ReceivedMessage) -> Result<OutgoingMessage, std::io::Error> {
+	Ok(OutgoingMessage {
+		data: std::mem::take(&mut request.data),
+		fds: std::mem::take(&mut request.fds),
+	})
+}
+
+/// Test that the client can be used with tower middleware.
+#[tokio::test]
+async fn client_with_middleware() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	tokio::spawn(serve_connection(server, service_fn(echo)));
+
+	let client = ServiceBuilder::new()
+		.timeout(Duration::from_secs(1))
+		.service(SeqpacketClient::new(client));
+	assert!(let Ok(response) = client.oneshot(OutgoingMessage::new("Hello!")).await);
+	assert!(response.data == b"Hello!");
+}
+
+/// Test that a service is driven for each accepted connection, and that file descriptors are passed along.
+#[tokio::test]
+async fn serve_listener() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	tokio::spawn(serve(listener, service_fn(echo)));
+
+	for _ in 0..2 {
+		assert!(let Ok(mut client) = SeqpacketClient::connect(&path).await);
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+		assert!(let Ok(a) = a.as_fd().try_clone_to_owned());
+		let request = OutgoingMessage {
+			data: b"fd".to_vec(),
+			fds: vec![a],
+		};
+		assert!(let Ok(mut response) = client.ready().await.unwrap().call(request).await);
+		assert!(response.data == b"fd");
+		assert!(response.fds.len() == 1);
+
+		// The returned file descriptor should refer to the same socket as `a`.
+		assert!(let Ok(a) = UnixSeqpacket::try_from(response.fds.remove(0)));
+		assert!(let Ok(5) = a.send(b"Hello").await);
+		let mut buffer = [0u8; 16];
+		assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+	}
+}
+
+/// Test that an error from the service is returned to the caller.
+#[tokio::test]
+async fn service_error() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	let service = service_fn(|_request: ReceivedMessage| async move { Err::<OutgoingMessage, _>("not allowed") });
+	tokio::spawn(serve_connection(server, service));
+
+	let client = SeqpacketClient::new(client);
+	assert!(let Err(RpcError::Remote(message)) = client.oneshot(OutgoingMessage::new("get")).await);
+	assert!(message == "not allowed");
+}
+
+/// Service that responds with the number of requests it handled before, and fails to become ready after `limit` requests.
+struct Counter {
+	count: u32,
+	limit: u32,
+}
+
+impl Service<ReceivedMessage> for Counter {
+	type Response = OutgoingMessage;
+	type Error = std::io::Error;
+	type Future = std::future::Ready<Result<OutgoingMessage, std::io::Error>>;
+
+	fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), std::io::Error>> {
+		if self.count < self.limit {
+			std::task::Poll::Ready(Ok(()))
+		} else {
+			std::task::Poll::Ready(Err(std::io::Error::other("limit reached")))
+		}
+	}
+
+	fn call(&mut self, _request: ReceivedMessage) -> Self::Future {
+		let response = OutgoingMessage::new(self.count.to_string());
+		self.count += 1;
+		std::future::ready(Ok(response))
+	}
+}
+
+/// Test that all requests of a connection go to the same service, which is polled for readiness before each request.
+#[tokio::test]
+async fn serve_connection_shares_service() {
+	assert!(let Ok((client, server)) = UnixSeqpacket::pair());
+	let server = tokio::spawn(serve_connection(server, Counter { count: 0, limit: 2 }));
+
+	let mut client = SeqpacketClient::new(client);
+	for expected in ["0", "1"] {
+		assert!(let Ok(response) = client.ready().await.unwrap().call(OutgoingMessage::new("get")).await);
+		assert!(response.data == expected.as_bytes());
+	}
+
+	// The service fails to become ready for the third request, which closes the connection.
+	assert!(let Err(RpcError::Disconnected) = client.ready().await.unwrap().call(OutgoingMessage::new("get")).await);
+	assert!(let Ok(Err(e)) = server.await);
+	assert!(e.to_string() == "limit reached");
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "tokio")]
+
 use assert2::assert;
 use tokio_seqpacket::UnixSeqpacket;
 
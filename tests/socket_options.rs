@@ -0,0 +1,60 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use tempfile::tempdir;
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that we can change the buffer sizes of a socket.
+#[tokio::test]
+async fn buffer_sizes() {
+	assert!(let Ok((a, _b)) = UnixSeqpacket::pair());
+
+	assert!(let Ok(()) = a.set_send_buffer_size(32 * 1024));
+	assert!(let Ok(size) = a.send_buffer_size());
+	assert!(size >= 32 * 1024);
+
+	assert!(let Ok(()) = a.set_recv_buffer_size(48 * 1024));
+	assert!(let Ok(size) = a.recv_buffer_size());
+	assert!(size >= 48 * 1024);
+}
+
+/// Test that the socket type and listening state are reported correctly.
+#[tokio::test]
+async fn socket_type() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(&path));
+	assert!(let Ok(libc::SOCK_SEQPACKET) = listener.socket_type());
+	assert!(let Ok(true) = listener.is_listener());
+
+	assert!(let Ok(socket) = UnixSeqpacket::connect(&path).await);
+	assert!(let Ok(libc::SOCK_SEQPACKET) = socket.socket_type());
+	assert!(let Ok(false) = socket.is_listener());
+}
+
+/// Test that `SO_PASSCRED` attaches credentials to every received message.
+#[cfg(all(feature = "non-portable", any(target_os = "linux", target_os = "android")))]
+#[tokio::test]
+async fn passcred() {
+	use tokio_seqpacket::ancillary::AncillaryMessage;
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(libc::AF_UNIX) = b.socket_domain());
+
+	assert!(let Ok(false) = b.passcred());
+	assert!(let Ok(()) = b.set_passcred(true));
+	assert!(let Ok(true) = b.passcred());
+
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+
+	let mut buffer = [0u8; 128];
+	let mut ancillary_buffer = [0u8; 128];
+	assert!(let Ok((msg_info, ancillary)) = b.recv_with_ancillary(&mut buffer, &mut ancillary_buffer).await);
+	assert!(msg_info.bytes_read() == 12);
+
+	let mut messages = ancillary.messages();
+	assert!(let Some(AncillaryMessage::Credentials(mut credentials)) = messages.next());
+	assert!(let Some(credentials) = credentials.next());
+	assert!(credentials.pid() == Some(std::process::id() as libc::pid_t));
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "futures")]
+
+use assert2::assert;
+use futures::{SinkExt, StreamExt};
+use std::io::{Read, Seek, Write};
+use tempfile::tempfile;
+use tokio_seqpacket::{OutgoingMessage, SeqpacketStream, UnixSeqpacket};
+
+/// Test sending and receiving messages through the sink and stream.
+#[tokio::test]
+async fn send_recv() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut a = SeqpacketStream::new(a);
+	let mut b = SeqpacketStream::new(b);
+
+	assert!(let Ok(()) = a.send(OutgoingMessage::new(b"Hello world!".to_vec())).await);
+	assert!(let Ok(()) = a.send(OutgoingMessage::new(b"Bye".to_vec())).await);
+
+	assert!(let Some(Ok(message)) = b.next().await);
+	assert!(message.data == b"Hello world!");
+	assert!(let Some(Ok(message)) = b.next().await);
+	assert!(message.data == b"Bye");
+}
+
+/// Test that file descriptors are passed along with the message.
+#[tokio::test]
+async fn pass_fd() {
+	assert!(let Ok(mut file) = tempfile());
+	assert!(let Ok(_) = file.write_all(b"Wie dit leest is gek."));
+	assert!(let Ok(()) = file.rewind());
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut a = SeqpacketStream::new(a);
+	let mut b = SeqpacketStream::new(b);
+
+	let mut message = OutgoingMessage::new(b"Here, have a file descriptor.".to_vec());
+	message.fds.push(file.into());
+	assert!(let Ok(()) = a.send(message).await);
+
+	assert!(let Some(Ok(message)) = b.next().await);
+	assert!(message.data == b"Here, have a file descriptor.");
+	assert!(message.fds.len() == 1);
+
+	let mut file = std::fs::File::from(message.fds.into_iter().next().unwrap());
+	let mut contents = Vec::new();
+	assert!(let Ok(_) = file.read_to_end(&mut contents));
+	assert!(contents == b"Wie dit leest is gek.");
+}
+
+/// Test that closing the sink ends the stream on the other side.
+#[tokio::test]
+async fn close_ends_stream() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut a = SeqpacketStream::new(a);
+	let mut b = SeqpacketStream::new(b);
+
+	assert!(let Ok(()) = a.send(OutgoingMessage::new(b"Hello world!".to_vec())).await);
+	assert!(let Ok(()) = a.close().await);
+
+	assert!(let Some(Ok(message)) = b.next().await);
+	assert!(message.data == b"Hello world!");
+	assert!(let None = b.next().await);
+	assert!(let None = b.next().await);
+}
+
+/// Test that an empty message ends the stream, even if the connection is still open.
+#[tokio::test]
+async fn empty_message_ends_stream() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut b = SeqpacketStream::new(b);
+
+	assert!(let Ok(0) = a.send(b"").await);
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+
+	assert!(let None = b.next().await);
+	assert!(let None = b.next().await);
+
+	// The connection is still open, so the next message can still be received from the socket itself.
+	let b = b.into_inner();
+	let mut buffer = [0; 32];
+	assert!(let Ok(info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..info.bytes_read()] == b"Hello world!");
+}
+
+/// Test that a message that does not fit in the receive buffer is reported as an error.
+#[tokio::test]
+async fn message_too_large() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut b = SeqpacketStream::with_capacity(b, 4, 64);
+
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+	assert!(let Ok(3) = a.send(b"Bye").await);
+
+	assert!(let Some(Err(e)) = b.next().await);
+	assert!(e.kind() == std::io::ErrorKind::InvalidData);
+	assert!(let Some(Ok(message)) = b.next().await);
+	assert!(message.data == b"Bye");
+}
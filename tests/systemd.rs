@@ -0,0 +1,146 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use tempfile::tempdir;
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Environment variable that tells a test it is running as socket activated child process.
+const CHILD_ENV: &str = "TOKIO_SEQPACKET_TEST_SYSTEMD_CHILD";
+
+/// Run a test from this binary in a child process, passing it file descriptors like systemd would.
+///
+/// The child test is responsible for setting `LISTEN_PID`, since we do not know the PID before spawning it.
+#[track_caller]
+fn run_child(test: &str, fds: &[RawFd], names: Option<&str>, dir: &Path) {
+	let mut command = std::process::Command::new(std::env::current_exe().unwrap());
+	command
+		.args(["--exact", test, "--test-threads=1", "--nocapture"])
+		.env(CHILD_ENV, dir)
+		.env("LISTEN_FDS", fds.len().to_string())
+		.env_remove("LISTEN_PID")
+		.env_remove("LISTEN_FDNAMES");
+	if let Some(names) = names {
+		command.env("LISTEN_FDNAMES", names);
+	}
+
+	// The closure runs between `fork()` and `exec()`, where it must not allocate.
+	// So prepare all buffers up front.
+	let fds = fds.to_vec();
+	let mut temp: Vec<RawFd> = vec![-1; fds.len()];
+	unsafe {
+		command.pre_exec(move || {
+			// Move the file descriptors out of the way first, so we don't clobber them with `dup2()`.
+			for (&fd, temp) in fds.iter().zip(temp.iter_mut()) {
+				*temp = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 100);
+				if *temp < 0 {
+					return Err(std::io::Error::last_os_error());
+				}
+			}
+			for (i, &fd) in temp.iter().enumerate() {
+				if libc::dup2(fd, 3 + i as RawFd) < 0 {
+					return Err(std::io::Error::last_os_error());
+				}
+			}
+			Ok(())
+		});
+	}
+
+	assert!(let Ok(output) = command.output());
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(output.status.success(), "stdout:\n{stdout}\nstderr:\n{stderr}");
+	assert!(stdout.contains("1 passed"), "stdout:\n{stdout}\nstderr:\n{stderr}");
+}
+
+/// Get the directory passed to a child test, or `None` if we are not running as child.
+fn child_dir() -> Option<std::path::PathBuf> {
+	let dir = std::env::var_os(CHILD_ENV)?;
+	// SAFETY: The child process only runs a single test, so nobody else is reading the environment.
+	unsafe {
+		std::env::set_var("LISTEN_PID", std::process::id().to_string());
+	}
+	Some(dir.into())
+}
+
+/// Test that we get no listeners when the process was not socket activated.
+#[tokio::test]
+async fn not_activated() {
+	if std::env::var_os(CHILD_ENV).is_some() {
+		return;
+	}
+	assert!(let Ok(listeners) = UnixSeqpacketListener::from_systemd());
+	assert!(listeners.is_empty());
+}
+
+/// Test that we can take the listeners passed by systemd, by name and all at once.
+#[tokio::test]
+async fn from_systemd() {
+	let dir = tempdir().unwrap();
+	assert!(let Ok(control) = UnixSeqpacketListener::bind(dir.path().join("control.sock")));
+	assert!(let Ok(data) = UnixSeqpacketListener::bind(dir.path().join("data.sock")));
+	run_child(
+		"from_systemd_child",
+		&[control.as_raw_fd(), data.as_raw_fd()],
+		Some("control:data"),
+		dir.path(),
+	);
+}
+
+#[tokio::test]
+async fn from_systemd_child() {
+	let Some(dir) = child_dir() else { return };
+
+	assert!(let Ok(mut listeners) = UnixSeqpacketListener::from_systemd_named("data"));
+	assert!(listeners.len() == 1);
+	let mut data = listeners.remove(0);
+	assert!(let Ok(address) = data.local_addr());
+	assert!(address.as_pathname() == Some(dir.join("data.sock").as_path()));
+
+	// The data socket was already taken, so only the control socket should remain.
+	assert!(let Ok(mut listeners) = UnixSeqpacketListener::from_systemd());
+	assert!(listeners.len() == 1);
+	let control = listeners.remove(0);
+	assert!(let Ok(address) = control.local_addr());
+	assert!(address.as_pathname() == Some(dir.join("control.sock").as_path()));
+
+	assert!(let Ok(listeners) = UnixSeqpacketListener::from_systemd());
+	assert!(listeners.is_empty());
+
+	// The listener should be usable with tokio.
+	let (server, client) = tokio::join!(data.accept(), UnixSeqpacket::connect(dir.join("data.sock")));
+	assert!(let Ok(_server) = server);
+	assert!(let Ok(_client) = client);
+}
+
+/// Test that file descriptors that are not seqpacket listeners are rejected.
+#[tokio::test]
+async fn from_systemd_invalid() {
+	let dir = tempdir().unwrap();
+	assert!(let Ok(stream) = std::os::unix::net::UnixListener::bind(dir.path().join("stream.sock")));
+	assert!(let Ok((socket, _peer)) = UnixSeqpacket::pair());
+	assert!(let Ok(listener) = UnixSeqpacketListener::bind(dir.path().join("listener.sock")));
+	run_child(
+		"from_systemd_invalid_child",
+		&[stream.as_raw_fd(), socket.as_raw_fd(), listener.as_raw_fd()],
+		Some("stream:socket:listener"),
+		dir.path(),
+	);
+}
+
+#[tokio::test]
+async fn from_systemd_invalid_child() {
+	let Some(_dir) = child_dir() else { return };
+
+	assert!(let Err(e) = UnixSeqpacketListener::from_systemd_named("stream"));
+	assert!(e.kind() == std::io::ErrorKind::InvalidInput);
+	assert!(let Err(e) = UnixSeqpacketListener::from_systemd_named("socket"));
+	assert!(e.kind() == std::io::ErrorKind::InvalidInput);
+	assert!(let Err(_) = UnixSeqpacketListener::from_systemd());
+
+	// A failed call should not take any file descriptors.
+	assert!(let Ok(listeners) = UnixSeqpacketListener::from_systemd_named("listener"));
+	assert!(listeners.len() == 1);
+}
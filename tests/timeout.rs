@@ -0,0 +1,60 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that a receive times out when no message arrives, without losing later messages.
+#[tokio::test]
+async fn recv_timeout() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let mut buffer = [0u8; 128];
+	assert!(let Err(e) = b.recv_timeout(&mut buffer, Duration::from_millis(10)).await);
+	assert!(e.kind() == std::io::ErrorKind::TimedOut);
+
+	assert!(let Ok(12) = a.send(b"Hello world!").await);
+	assert!(let Ok(msg_info) = b.recv_timeout(&mut buffer, Duration::from_secs(1)).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+}
+
+/// Test that a send times out when the send buffer is full, without sending a partial message.
+#[tokio::test]
+async fn send_timeout() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	// Fill the send buffer.
+	let mut sent = 0;
+	loop {
+		match a.send_timeout(b"Hello world!", Duration::from_millis(10)).await {
+			Ok(_) => sent += 1,
+			Err(e) => {
+				assert!(e.kind() == std::io::ErrorKind::TimedOut);
+				break;
+			},
+		}
+	}
+
+	// All messages that were sent should be intact.
+	let mut buffer = [0u8; 128];
+	for _ in 0..sent {
+		assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+	}
+	assert!(let Err(_) = b.recv_timeout(&mut buffer, Duration::from_millis(10)).await);
+}
+
+/// Test that an accept times out when nobody connects.
+#[tokio::test]
+async fn accept_timeout() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+	assert!(let Err(e) = listener.accept_timeout(Duration::from_millis(10)).await);
+	assert!(e.kind() == std::io::ErrorKind::TimedOut);
+
+	assert!(let Ok(_client) = UnixSeqpacket::connect_timeout(&path, Duration::from_secs(1)).await);
+	assert!(let Ok(_server) = listener.accept_timeout(Duration::from_secs(1)).await);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "tokio")]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use tempfile::tempdir;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+
+/// Test that `try_recv()` and `try_peek()` return `WouldBlock` without a message, and messages once they arrive.
+#[tokio::test]
+async fn try_send_recv() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+
+	let mut buffer = [0u8; 128];
+	assert!(let Err(e) = b.try_recv(&mut buffer));
+	assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+	assert!(let Err(e) = b.try_peek(&mut buffer));
+	assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+
+	a.as_async_fd().writable().await.unwrap().retain_ready();
+	assert!(let Ok(12) = a.try_send(b"Hello world!"));
+
+	b.as_async_fd().readable().await.unwrap().retain_ready();
+	assert!(let Ok(msg_info) = b.try_peek(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+	assert!(let Ok(msg_info) = b.try_recv(&mut buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+
+	// The socket should no longer be readable, and the async functions should still work.
+	assert!(let Err(e) = b.try_recv(&mut buffer));
+	assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+	assert!(let Ok(7) = a.send(b"Goodbye").await);
+	assert!(let Ok(msg_info) = b.recv(&mut buffer).await);
+	assert!(&buffer[..msg_info.bytes_read()] == b"Goodbye");
+}
+
+/// Test that `try_send()` returns `WouldBlock` when the send buffer is full.
+#[tokio::test]
+async fn try_send_full() {
+	assert!(let Ok((a, _b)) = UnixSeqpacket::pair());
+	a.as_async_fd().writable().await.unwrap().retain_ready();
+
+	loop {
+		match a.try_send(b"Hello world!") {
+			Ok(_) => continue,
+			Err(e) => {
+				assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+				break;
+			},
+		}
+	}
+}
+
+/// Test that we can send and receive file descriptors with the `try_` functions.
+#[tokio::test]
+async fn try_send_recv_with_ancillary() {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok((c, _d)) = UnixSeqpacket::pair());
+
+	let mut ancillary_buffer = [0u8; 64];
+	let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+	assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+	a.as_async_fd().writable().await.unwrap().retain_ready();
+	assert!(let Ok(12) = a.try_send_with_ancillary(b"Hello world!", &mut ancillary));
+
+	let mut buffer = [0u8; 128];
+	let mut ancillary_buffer = [0u8; 64];
+	b.as_async_fd().readable().await.unwrap().retain_ready();
+	assert!(let Ok((msg_info, ancillary)) = b.try_recv_with_ancillary(&mut buffer, &mut ancillary_buffer));
+	assert!(&buffer[..msg_info.bytes_read()] == b"Hello world!");
+	let mut messages = ancillary.into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(_fd) = fds.next());
+	assert!(let None = fds.next());
+}
+
+/// Test that `try_accept()` returns `WouldBlock` without a pending connection.
+#[tokio::test]
+async fn try_accept() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+	assert!(let Err(e) = listener.try_accept());
+	assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+
+	assert!(let Ok(_client) = UnixSeqpacket::connect(&path).await);
+	// Give the runtime a chance to notice that the listener is readable.
+	let server = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+		loop {
+			match listener.try_accept() {
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+					tokio::time::sleep(std::time::Duration::from_millis(1)).await
+				},
+				result => return result,
+			}
+		}
+	});
+	assert!(let Ok(Ok(_server)) = server.await);
+	assert!(let Err(e) = listener.try_accept());
+	assert!(e.kind() == std::io::ErrorKind::WouldBlock);
+}
@@ -0,0 +1,147 @@
+#![cfg(all(feature = "uring", target_os = "linux"))]
+
+use assert2::assert;
+use std::os::fd::AsFd;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio_seqpacket::ancillary::{AncillaryMessageWriter, OwnedAncillaryMessage};
+use tokio_seqpacket::{UnixSeqpacket, UnixSeqpacketListener, UringSeqpacket};
+
+/// Test that we can exchange messages with owned buffers.
+#[tokio::test]
+async fn send_recv() {
+	assert!(let Ok((a, b)) = UringSeqpacket::pair());
+
+	let (result, buffer) = a.send(b"Hello world!".to_vec()).await;
+	assert!(let Ok(12) = result);
+	assert!(buffer == b"Hello world!");
+
+	let (result, buffer) = b.recv(Vec::with_capacity(128)).await;
+	assert!(let Ok(msg_info) = result);
+	assert!(msg_info.bytes_read() == 12);
+	assert!(!msg_info.truncated());
+	assert!(buffer == b"Hello world!");
+
+	// Messages that do not fit in the capacity of the buffer are truncated.
+	let (result, _buffer) = b.send(b"Goodbye".to_vec()).await;
+	assert!(let Ok(7) = result);
+	let (result, buffer) = a.recv(Vec::with_capacity(4)).await;
+	assert!(let Ok(msg_info) = result);
+	assert!(msg_info.truncated());
+	assert!(buffer == b"Good");
+}
+
+/// Test that we can exchange messages with multiple owned buffers.
+#[tokio::test]
+async fn send_recv_vectored() {
+	assert!(let Ok((a, b)) = UringSeqpacket::pair());
+
+	let (result, buffers) = a.send_vectored(vec![b"Hello".to_vec(), b" world!".to_vec()]).await;
+	assert!(let Ok(12) = result);
+	assert!(buffers == [b"Hello".to_vec(), b" world!".to_vec()]);
+
+	let (result, buffers) = b
+		.recv_vectored(vec![
+			Vec::with_capacity(8),
+			Vec::with_capacity(8),
+			Vec::with_capacity(8),
+		])
+		.await;
+	assert!(let Ok(msg_info) = result);
+	assert!(msg_info.bytes_read() == 12);
+	assert!(!msg_info.truncated());
+	assert!(buffers == [b"Hello wo".to_vec(), b"rld!".to_vec(), Vec::new()]);
+}
+
+/// Test that we can pass file descriptors with io_uring.
+#[tokio::test]
+async fn send_recv_with_ancillary() {
+	assert!(let Ok((a, b)) = UringSeqpacket::pair());
+	assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+
+	let mut ancillary_buffer = [0u8; 64];
+	let mut ancillary = AncillaryMessageWriter::new(&mut ancillary_buffer);
+	assert!(let Ok(()) = ancillary.add_fds([c.as_fd()]));
+	let (result, _buffer) = a.send_with_ancillary(b"Hello world!".to_vec(), &mut ancillary).await;
+	assert!(let Ok(12) = result);
+
+	let mut ancillary_buffer = [0u8; 64];
+	let (result, buffer) = b
+		.recv_with_ancillary(Vec::with_capacity(128), &mut ancillary_buffer)
+		.await;
+	assert!(let Ok((msg_info, ancillary)) = result);
+	assert!(!msg_info.ancillary_truncated());
+	assert!(buffer == b"Hello world!");
+	let mut messages = ancillary.into_messages();
+	assert!(let Some(OwnedAncillaryMessage::FileDescriptors(mut fds)) = messages.next());
+	assert!(let Some(fd) = fds.next());
+	assert!(let None = fds.next());
+	assert!(let None = messages.next());
+
+	// The received file descriptor should refer to the same socket as `c`.
+	assert!(let Ok(c) = UnixSeqpacket::try_from(fd));
+	assert!(let Ok(5) = c.send(b"Hello").await);
+	let mut buffer = [0u8; 16];
+	assert!(let Ok(msg_info) = d.recv(&mut buffer).await);
+	assert!(msg_info.bytes_read() == 5);
+	assert!(&buffer[..5] == b"Hello");
+}
+
+/// Test that we can convert a connected tokio socket to a `UringSeqpacket`.
+#[tokio::test]
+async fn convert_from_tokio() {
+	let dir = tempdir().unwrap();
+	let path = dir.path().join("listener.sock");
+	assert!(let Ok(mut listener) = UnixSeqpacketListener::bind(&path));
+
+	let (client, server) = tokio::join!(UringSeqpacket::connect(&path), listener.accept());
+	assert!(let Ok(client) = client);
+	assert!(let Ok(server) = server);
+	assert!(let Ok(server) = UringSeqpacket::try_from(server));
+
+	let (result, _buffer) = client.send(b"Hello".to_vec()).await;
+	assert!(let Ok(5) = result);
+	let (result, buffer) = server.recv(Vec::with_capacity(16)).await;
+	assert!(let Ok(_) = result);
+	assert!(buffer == b"Hello");
+}
+
+/// Test that dropping a pending receive cancels it, and that the socket remains usable.
+#[tokio::test]
+async fn cancel_recv() {
+	assert!(let Ok((a, b)) = UringSeqpacket::pair());
+
+	let recv = b.recv(Vec::with_capacity(16));
+	assert!(let Err(_) = tokio::time::timeout(Duration::from_millis(10), recv).await);
+
+	let (result, _buffer) = a.send(b"Hello".to_vec()).await;
+	assert!(let Ok(5) = result);
+	let (result, buffer) = b.recv(Vec::with_capacity(16)).await;
+	assert!(let Ok(_) = result);
+	assert!(buffer == b"Hello");
+}
+
+/// Test that concurrent operations on the same socket all complete.
+#[tokio::test]
+async fn concurrent_recv() {
+	assert!(let Ok((a, b)) = UringSeqpacket::pair());
+
+	let recv = futures::future::join_all((0..4).map(|_| b.recv(Vec::with_capacity(16))));
+	let send = async {
+		for i in 0..4u8 {
+			let (result, _buffer) = a.send(vec![i]).await;
+			assert!(let Ok(1) = result);
+		}
+	};
+	let (received, ()) = tokio::join!(recv, send);
+	let mut received: Vec<u8> = received
+		.into_iter()
+		.map(|(result, buffer)| {
+			assert!(let Ok(_) = result);
+			assert!(buffer.len() == 1);
+			buffer[0]
+		})
+		.collect();
+	received.sort();
+	assert!(received == [0, 1, 2, 3]);
+}
@@ -0,0 +1,213 @@
+#![cfg(any(feature = "postcard", feature = "bincode", feature = "json"))]
+
+use assert2::assert;
+use serde::{Deserialize, Serialize};
+use tokio_seqpacket::format::Format;
+use tokio_seqpacket::UnixSeqpacket;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Request {
+	id: u32,
+	name: String,
+	tags: Vec<String>,
+}
+
+fn request() -> Request {
+	Request {
+		id: 7,
+		name: "hello".into(),
+		tags: vec!["a".into(), "b".into()],
+	}
+}
+
+/// Send and receive a value with the given format.
+async fn round_trip<F: Format + Copy>(format: F) {
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(_) = a.send_value(format, &request()).await);
+	assert!(let Ok(_) = a.send_value(format, &request()).await);
+
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(value) = b.recv_value::<_, Request>(format, &mut buffer).await);
+	assert!(value == request());
+
+	// Each value is a separate message.
+	assert!(let Ok(value) = b.recv_value::<_, Request>(format, &mut buffer).await);
+	assert!(value == request());
+}
+
+/// Test that values can be sent and received with postcard.
+#[cfg(feature = "postcard")]
+#[tokio::test]
+async fn postcard() {
+	round_trip(tokio_seqpacket::format::Postcard).await;
+}
+
+/// Test that values can be sent and received with bincode.
+#[cfg(feature = "bincode")]
+#[tokio::test]
+async fn bincode() {
+	round_trip(tokio_seqpacket::format::Bincode).await;
+}
+
+/// Test that values can be sent and received with JSON.
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn json() {
+	round_trip(tokio_seqpacket::format::Json).await;
+}
+
+/// Test that the same buffer can be reused to send multiple values.
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn send_value_with_buffer() {
+	use tokio_seqpacket::format::Json;
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	let mut send_buffer = Vec::new();
+	assert!(let Ok(_) = a.send_value_with_buffer(Json, &request(), &mut send_buffer).await);
+	let capacity = send_buffer.capacity();
+	assert!(let Ok(_) = a.send_value_with_buffer(Json, &request(), &mut send_buffer).await);
+	assert!(send_buffer.capacity() == capacity);
+
+	let mut buffer = [0u8; 128];
+	assert!(let Ok(value) = b.recv_value::<_, Request>(Json, &mut buffer).await);
+	assert!(value == request());
+	assert!(let Ok(value) = b.recv_value::<_, Request>(Json, &mut buffer).await);
+	assert!(value == request());
+}
+
+/// Test that a message that does not fit in the receive buffer results in a truncation error.
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn truncated() {
+	use tokio_seqpacket::format::Json;
+	use tokio_seqpacket::ValueError;
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(_) = a.send_value(Json, &request()).await);
+
+	let mut buffer = [0u8; 8];
+	let result = b.recv_value::<_, Request>(Json, &mut buffer).await;
+	assert!(let Err(ValueError::Truncated { buffer_len: 8 }) = result);
+}
+
+/// Test that a message that is not a valid value results in a decode error.
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn decode_error() {
+	use tokio_seqpacket::format::Json;
+	use tokio_seqpacket::ValueError;
+
+	assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+	assert!(let Ok(_) = a.send(b"not json").await);
+
+	let mut buffer = [0u8; 128];
+	let result = b.recv_value::<_, Request>(Json, &mut buffer).await;
+	assert!(let Err(ValueError::Decode(_)) = result);
+}
+
+#[cfg(feature = "json")]
+mod fds {
+	use assert2::assert;
+	use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+	use tokio_seqpacket::format::Json;
+	use tokio_seqpacket::{UnixSeqpacket, ValueError};
+
+	#[derive(serde::Serialize)]
+	struct SendTwo<'a> {
+		name: &'a str,
+		#[serde(with = "tokio_seqpacket::format::fd")]
+		file: BorrowedFd<'a>,
+		#[serde(with = "tokio_seqpacket::format::fd")]
+		extra: BorrowedFd<'a>,
+	}
+
+	#[derive(serde::Serialize, serde::Deserialize)]
+	struct OpenResult {
+		name: String,
+		#[serde(with = "tokio_seqpacket::format::fd")]
+		file: OwnedFd,
+	}
+
+	/// Test that file descriptors in a value are passed as ancillary data.
+	#[tokio::test]
+	async fn send_recv_fd() {
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+		assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+
+		let value = OpenResult {
+			name: "socket".into(),
+			file: c.into(),
+		};
+		assert!(let Ok(_) = a.send_value(Json, &value).await);
+		drop(value);
+
+		let mut buffer = [0u8; 128];
+		let mut ancillary_buffer = [0u8; 64];
+		let result = b
+			.recv_value_with_fds::<_, OpenResult>(Json, &mut buffer, &mut ancillary_buffer)
+			.await;
+		assert!(let Ok(received) = result);
+		assert!(received.name == "socket");
+
+		// The received file descriptor should refer to the same socket as `c`.
+		assert!(let Ok(c) = UnixSeqpacket::try_from(received.file));
+		assert!(let Ok(5) = c.send(b"Hello").await);
+		assert!(let Ok(msg_info) = d.recv(&mut buffer).await);
+		assert!(&buffer[..msg_info.bytes_read()] == b"Hello");
+	}
+
+	/// Test that received file descriptors that are not claimed by the value are closed.
+	#[tokio::test]
+	async fn unclaimed_fds_are_closed() {
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+		assert!(let Ok((c, d)) = UnixSeqpacket::pair());
+		assert!(let Ok((e, _f)) = UnixSeqpacket::pair());
+
+		let value = SendTwo {
+			name: "two",
+			file: e.as_fd(),
+			extra: c.as_fd(),
+		};
+		assert!(let Ok(_) = a.send_value(Json, &value).await);
+		drop(c);
+
+		// `OpenResult` only claims the file descriptor of the `file` field.
+		let mut buffer = [0u8; 128];
+		let mut ancillary_buffer = [0u8; 64];
+		let result = b
+			.recv_value_with_fds::<_, OpenResult>(Json, &mut buffer, &mut ancillary_buffer)
+			.await;
+		assert!(let Ok(_) = result);
+
+		// The last copy of `c` is closed, so `d` sees the end of the connection.
+		assert!(let Ok(msg_info) = d.recv(&mut buffer).await);
+		assert!(msg_info.bytes_read() == 0);
+	}
+
+	/// Test that indices that do not refer to a received file descriptor are rejected.
+	#[tokio::test]
+	async fn out_of_range_index() {
+		assert!(let Ok((a, b)) = UnixSeqpacket::pair());
+		assert!(let Ok(_) = a.send(br#"{"name": "bad", "file": 3}"#).await);
+
+		let mut buffer = [0u8; 128];
+		let mut ancillary_buffer = [0u8; 64];
+		let result = b
+			.recv_value_with_fds::<_, OpenResult>(Json, &mut buffer, &mut ancillary_buffer)
+			.await;
+		assert!(let Err(ValueError::Decode(e)) = result);
+		assert!(e.to_string().contains("out of range"));
+	}
+
+	/// Test that file descriptors can not be serialized outside of `send_value()`.
+	#[test]
+	fn serialize_without_socket() {
+		assert!(let Ok((c, _d)) = std::os::unix::net::UnixDatagram::pair());
+		let value = OpenResult {
+			name: "socket".into(),
+			file: c.into(),
+		};
+		assert!(let Err(_) = serde_json::to_vec(&value));
+	}
+}